@@ -0,0 +1,2045 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{sync_channel, SyncSender},
+        Arc, Mutex,
+    },
+};
+
+use clap::ArgEnum;
+use tokio::{sync::Semaphore, task::JoinHandle};
+
+use crate::{
+    cycles::cycle_statistics,
+    error::{Error, Position, Result},
+    estimator::{Direction, Pattern, Step},
+    frequent,
+    header::{normalise_label, EdgeColumns, VertexColumns},
+    hierarchy::LabelHierarchy,
+    input::{discover, discover_batches, read_header, InputFile, Record, Row, Source},
+    property::{civil_from_days, parse_temporal, ColumnCounts, Detail, Property},
+    schema::{EdgeMapping, Entity, ForeignKey, Schema, VertexMapping},
+    sketch::hash_vertex,
+    statistics::{
+        merge_characteristic_sets, CharacteristicSet, ColumnStatistics, DegreeDistribution,
+        DistinctEndpoints, Distribution, EndpointSketches, SkippedRows, Statistics,
+    },
+};
+
+/// Number of records handed to a worker at once.
+const BATCH_SIZE: usize = 4096;
+
+/// Default number of buckets of property histograms.
+const HISTOGRAM_BUCKETS: usize = 32;
+/// Default number of most common values listed per string property.
+const MOST_COMMON: usize = 10;
+/// Largest number of most common values listed per string property, the number
+/// of values tracked per column.
+pub const MAX_MOST_COMMON: usize = frequent::CAPACITY;
+/// Name of the column time series are bucketed by.
+const CREATION_DATE: &str = "creationDate";
+/// In approximate mode, paths are counted through one in this many vertices,
+/// those whose `hash_vertex` is a multiple of it.
+const PATH_SAMPLE_RATE: u64 = 64;
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OnError {
+    /// Skip the row and tally it in `skipped_rows`
+    Skip,
+    /// Abort the import
+    #[default]
+    Fail,
+}
+
+/// Width of the buckets of `Statistics::vertex_series` and `edge_series`.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Day,
+    Month,
+    Year,
+}
+
+impl Granularity {
+    /// Returns the bucket of a time in milliseconds since the Unix epoch, e.g.
+    /// `2010-05-01`, `2010-05` or `2010`.
+    fn bucket(self, millis: i64) -> String {
+        let (year, month, day) = civil_from_days(millis.div_euclid(86_400_000));
+        match self {
+            Granularity::Day => format!("{:04}-{:02}-{:02}", year, month, day),
+            Granularity::Month => format!("{:04}-{:02}", year, month),
+            Granularity::Year => format!("{:04}", year),
+        }
+    }
+}
+
+/// A step of an import, reported to the callback set with
+/// `ContextBuilder::progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress<'a> {
+    /// The file is about to be read.
+    Import(&'a Path),
+    /// The file is left out, as the schema ignores it.
+    Ignore(&'a Path),
+}
+
+/// Passes the `Progress` of an import to the callback set with
+/// `ContextBuilder::progress`, from whichever thread reads a file.
+#[derive(Clone, Default)]
+struct Reporter(Option<Arc<ProgressFn>>);
+
+type ProgressFn = dyn Fn(Progress) + Send + Sync;
+
+impl Reporter {
+    fn report(&self, progress: Progress) {
+        if let Some(callback) = &self.0 {
+            callback(progress);
+        }
+    }
+}
+
+impl std::fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Reporter(Some(_))"),
+            None => f.write_str("Reporter(None)"),
+        }
+    }
+}
+
+/// What `Counts` collect besides the cardinalities, shared by the counts of
+/// all workers.
+#[derive(Debug, Default)]
+struct Options {
+    /// Sketch edge endpoints instead of tracking every vertex.
+    approx: bool,
+    /// Count length-2 paths, which in approximate mode requires the degrees of
+    /// a sample of the vertices.
+    paths: bool,
+    /// Concrete vertex and edge labels of the homogeneous triples whose edges
+    /// are kept to count cycles.
+    cycles: Vec<(String, String)>,
+    /// Count vertices and edges per bucket of their `creationDate`.
+    time_series: Option<Granularity>,
+    /// Collect statistics of the property columns, which in exact mode keeps
+    /// the hashes of the distinct values of every column.
+    properties: bool,
+}
+
+/// Partial counts keyed by concrete labels only; they are rolled up to the
+/// ancestors of each label once the label hierarchy is complete.
+#[derive(Debug, Default)]
+struct Counts {
+    options: Arc<Options>,
+    vertices: HashMap<String, u64>,
+    edges: HashMap<String, HashMap<String, HashMap<String, EdgeCounts>>>,
+    /// Number of vertices and edges per time series bucket.
+    vertex_series: HashMap<String, BucketCounts>,
+    edge_series: HashMap<String, HashMap<String, HashMap<String, BucketCounts>>>,
+    vertex_properties: PropertyCounts,
+    /// Edge properties per source and edge label, then keyed like vertex
+    /// properties by destination label.
+    edge_properties: HashMap<String, HashMap<String, PropertyCounts>>,
+    skipped_rows: SkippedRows,
+}
+
+/// Property columns per label and property name.
+type PropertyCounts = HashMap<String, HashMap<String, ColumnCounts>>;
+
+/// Edges of one concrete `(src_label, edge_label, dst_label)` triple.
+#[derive(Debug)]
+struct EdgeCounts {
+    count: u64,
+    endpoints: Endpoints,
+    /// Degrees of a sample of the endpoints, kept in approximate mode when
+    /// paths are counted.
+    sampled: Option<SampledDegrees>,
+    /// Source and destination ids of every edge, kept to count cycles.
+    pairs: Option<Vec<(u64, u64)>>,
+}
+
+#[derive(Debug)]
+enum Endpoints {
+    Exact {
+        /// Number of edges per source vertex id.
+        out_degrees: HashMap<u64, u32>,
+        /// Number of edges per destination vertex id.
+        in_degrees: HashMap<u64, u32>,
+    },
+    Approx(EndpointSketches),
+}
+
+/// Number of rows per time series bucket.
+type BucketCounts = BTreeMap<String, u64>;
+
+/// Number of edges per vertex id.
+type VertexDegrees = HashMap<u64, u32>;
+
+#[derive(Debug, Default)]
+struct SampledDegrees {
+    out_degrees: VertexDegrees,
+    in_degrees: VertexDegrees,
+}
+
+fn add_degrees(degrees: &mut VertexDegrees, other: VertexDegrees) {
+    for (id, degree) in other {
+        *degrees.entry(id).or_insert(0) += degree;
+    }
+}
+
+impl EdgeCounts {
+    fn new(approx: bool, paths: bool, cycles: bool) -> Self {
+        let endpoints = match approx {
+            true => Endpoints::Approx(EndpointSketches::default()),
+            false => Endpoints::Exact {
+                out_degrees: HashMap::new(),
+                in_degrees: HashMap::new(),
+            },
+        };
+        EdgeCounts {
+            count: 0,
+            endpoints,
+            sampled: (approx && paths).then(SampledDegrees::default),
+            pairs: cycles.then(Vec::new),
+        }
+    }
+
+    fn merge(&mut self, other: EdgeCounts) {
+        self.count += other.count;
+        match (&mut self.endpoints, other.endpoints) {
+            (
+                Endpoints::Exact {
+                    out_degrees,
+                    in_degrees,
+                },
+                Endpoints::Exact {
+                    out_degrees: other_out_degrees,
+                    in_degrees: other_in_degrees,
+                },
+            ) => {
+                add_degrees(out_degrees, other_out_degrees);
+                add_degrees(in_degrees, other_in_degrees);
+            }
+            (Endpoints::Approx(sketches), Endpoints::Approx(other_sketches)) => {
+                sketches.merge(&other_sketches)
+            }
+            _ => unreachable!("counts of one import are either all exact or all approximate"),
+        }
+        if let (Some(sampled), Some(other_sampled)) = (&mut self.sampled, other.sampled) {
+            add_degrees(&mut sampled.out_degrees, other_sampled.out_degrees);
+            add_degrees(&mut sampled.in_degrees, other_sampled.in_degrees);
+        }
+        if let (Some(pairs), Some(other_pairs)) = (&mut self.pairs, other.pairs) {
+            pairs.extend(other_pairs);
+        }
+    }
+
+    /// Out- and in-degrees per vertex id of all endpoints, or of the sampled
+    /// ones in approximate mode.
+    fn degrees(&self) -> Option<(&VertexDegrees, &VertexDegrees)> {
+        match (&self.endpoints, &self.sampled) {
+            (
+                Endpoints::Exact {
+                    out_degrees,
+                    in_degrees,
+                },
+                _,
+            ) => Some((out_degrees, in_degrees)),
+            (Endpoints::Approx(_), Some(sampled)) => {
+                Some((&sampled.out_degrees, &sampled.in_degrees))
+            }
+            (Endpoints::Approx(_), None) => None,
+        }
+    }
+}
+
+/// Edges of one concrete triple as seen from the vertices of one of its
+/// endpoint labels.
+struct Incident<'a> {
+    label: &'a str,
+    /// `Outgoing` if the vertices are the sources of the edges.
+    direction: Direction,
+    /// Concrete label of the other endpoints.
+    other: &'a str,
+    degrees: &'a VertexDegrees,
+}
+
+/// Endpoints of the edges of the concrete triples below a rolled-up triple,
+/// with the concrete source and destination label of each.
+type Rollup<'a> = Vec<(&'a str, &'a str, &'a Endpoints)>;
+
+/// Out- or in-degrees of the vertices of a rolled-up triple with exactly
+/// tracked endpoints, summed per vertex over its concrete triples.
+///
+/// Vertices of different concrete labels are distinct even if their ids are
+/// not, so only the degrees of endpoints of the same label are summed, and
+/// those of a label with a single triple are taken as they are.
+fn rolled_up_degrees(rollup: &Rollup, direction: Direction) -> Vec<u64> {
+    let mut by_label: HashMap<&str, Vec<&VertexDegrees>> = HashMap::new();
+    for &(src_label, dst_label, endpoints) in rollup {
+        if let Endpoints::Exact {
+            out_degrees,
+            in_degrees,
+        } = endpoints
+        {
+            let (label, degrees) = match direction {
+                Direction::Outgoing => (src_label, out_degrees),
+                Direction::Incoming => (dst_label, in_degrees),
+            };
+            by_label.entry(label).or_default().push(degrees);
+        }
+    }
+    let mut rolled_up = Vec::new();
+    for maps in by_label.into_values() {
+        match maps[..] {
+            [degrees] => rolled_up.extend(degrees.values().map(|&degree| degree as u64)),
+            _ => {
+                let mut summed: HashMap<u64, u64> = HashMap::new();
+                for degrees in maps {
+                    for (&id, &degree) in degrees {
+                        *summed.entry(id).or_insert(0) += degree as u64;
+                    }
+                }
+                rolled_up.extend(summed.into_values());
+            }
+        }
+    }
+    rolled_up
+}
+
+/// Like `map.entry(key.to_owned()).or_insert_with(default)`, without allocating
+/// a key for entries that already exist.
+fn entry_with<'a, V>(
+    map: &'a mut HashMap<String, V>,
+    key: &str,
+    default: impl FnOnce() -> V,
+) -> &'a mut V {
+    if !map.contains_key(key) {
+        map.insert(key.to_owned(), default());
+    }
+    map.get_mut(key).expect("entry exists")
+}
+
+fn entry<'a, V: Default>(map: &'a mut HashMap<String, V>, key: &str) -> &'a mut V {
+    entry_with(map, key, V::default)
+}
+
+fn add_properties(
+    map: &mut PropertyCounts,
+    approx: bool,
+    label: &str,
+    row: &Row,
+    properties: &[Property],
+) {
+    let columns = entry(map, label);
+    for property in properties {
+        // missing trailing fields are nulls
+        let value = row.record.get(property.index).unwrap_or_default();
+        entry_with(columns, &property.name, || {
+            ColumnCounts::new(property.property_type, approx)
+        })
+        .add(value);
+    }
+}
+
+fn merge_properties(map: &mut PropertyCounts, other: PropertyCounts) {
+    for (label, columns) in other {
+        let label_entry = map.entry(label).or_default();
+        for (name, column) in columns {
+            match label_entry.get_mut(&name) {
+                Some(entry) => entry.merge(&column),
+                None => {
+                    label_entry.insert(name, column);
+                }
+            }
+        }
+    }
+}
+
+/// Merges `columns` into the columns of each of `keys`, copying them for all
+/// but the last key and moving them into that one.
+fn roll_up_columns<K: Eq + Hash>(
+    map: &mut HashMap<K, HashMap<String, ColumnCounts>>,
+    mut keys: Vec<K>,
+    columns: HashMap<String, ColumnCounts>,
+) {
+    let last = match keys.pop() {
+        Some(last) => last,
+        None => return,
+    };
+    for key in keys {
+        let entry = map.entry(key).or_default();
+        for (name, column) in &columns {
+            match entry.get_mut(name) {
+                Some(entry) => entry.merge(column),
+                None => {
+                    entry.insert(name.clone(), column.clone());
+                }
+            }
+        }
+    }
+    let entry = map.entry(last).or_default();
+    for (name, column) in columns {
+        match entry.get_mut(&name) {
+            Some(entry) => entry.merge(&column),
+            None => {
+                entry.insert(name, column);
+            }
+        }
+    }
+}
+
+fn owned(labels: Vec<&str>) -> Vec<String> {
+    labels.into_iter().map(str::to_owned).collect()
+}
+
+fn finish_properties(
+    columns: impl IntoIterator<Item = (String, ColumnCounts)>,
+    detail: Detail,
+) -> HashMap<String, ColumnStatistics> {
+    columns
+        .into_iter()
+        .map(|(name, column)| (name, column.finish(detail)))
+        .collect()
+}
+
+/// Groups the vertices of one concrete label by the labels of their outgoing
+/// `edges`, given per edge and destination label.
+fn characteristic_sets(
+    edges: &HashMap<String, HashMap<String, EdgeCounts>>,
+) -> Vec<CharacteristicSet> {
+    let mut vertices: HashMap<u64, BTreeMap<&str, u64>> = HashMap::new();
+    for (edge_label, dsts) in edges {
+        for counts in dsts.values() {
+            if let Endpoints::Exact { out_degrees, .. } = &counts.endpoints {
+                for (&id, &degree) in out_degrees {
+                    *vertices
+                        .entry(id)
+                        .or_default()
+                        .entry(edge_label)
+                        .or_insert(0) += degree as u64;
+                }
+            }
+        }
+    }
+
+    // total degree per label of the vertices of each set
+    let mut sets: HashMap<Vec<&str>, (u64, Vec<u64>)> = HashMap::new();
+    for degrees in vertices.into_values() {
+        let (count, totals) = sets
+            .entry(degrees.keys().copied().collect())
+            .or_insert_with(|| (0, vec![0; degrees.len()]));
+        *count += 1;
+        for (total, degree) in totals.iter_mut().zip(degrees.into_values()) {
+            *total += degree;
+        }
+    }
+    sets.into_iter()
+        .map(|(labels, (count, totals))| CharacteristicSet {
+            count,
+            multiplicities: labels
+                .into_iter()
+                .zip(totals)
+                .map(|(label, total)| (label.to_owned(), total as f64 / count as f64))
+                .collect(),
+        })
+        .collect()
+}
+
+fn merge_series<T: std::ops::AddAssign + Default>(
+    series: &mut BTreeMap<String, T>,
+    other: impl IntoIterator<Item = (String, T)>,
+) {
+    for (bucket, count) in other {
+        *series.entry(bucket).or_default() += count;
+    }
+}
+
+/// Inserts `value` under the `[src_label, edge_label, dst_label]` triple.
+fn insert_triple<V>(
+    map: &mut HashMap<String, HashMap<String, HashMap<String, V>>>,
+    [src_label, edge_label, dst_label]: [&str; 3],
+    value: V,
+) {
+    entry(entry(map, src_label), edge_label).insert(dst_label.to_owned(), value);
+}
+
+impl Counts {
+    fn new(options: Arc<Options>) -> Self {
+        Counts {
+            options,
+            ..Counts::default()
+        }
+    }
+
+    /// Returns empty counts with the same options.
+    fn empty(&self) -> Self {
+        Counts::new(self.options.clone())
+    }
+
+    fn add_vertex(&mut self, label: &str) {
+        *entry(&mut self.vertices, label) += 1;
+    }
+
+    /// Returns the time series bucket of a row, if time series are counted and
+    /// the row has a valid `creationDate`.
+    fn bucket(&self, row: &Row, properties: &[Property]) -> Option<String> {
+        let granularity = self.options.time_series?;
+        let property = properties
+            .iter()
+            .find(|property| property.name == CREATION_DATE)?;
+        let millis = parse_temporal(row.record.get(property.index)?)?;
+        Some(granularity.bucket(millis))
+    }
+
+    fn add_vertex_bucket(&mut self, label: &str, bucket: &str) {
+        *entry(&mut self.vertex_series, label)
+            .entry(bucket.to_owned())
+            .or_default() += 1;
+    }
+
+    fn add_edge_bucket(&mut self, [src_label, edge_label, dst_label]: [&str; 3], bucket: &str) {
+        let series = entry(
+            entry(entry(&mut self.edge_series, src_label), edge_label),
+            dst_label,
+        );
+        *series.entry(bucket.to_owned()).or_default() += 1;
+    }
+
+    fn add_vertex_properties(&mut self, label: &str, row: &Row, properties: &[Property]) {
+        if !self.options.properties {
+            return;
+        }
+        add_properties(
+            &mut self.vertex_properties,
+            self.options.approx,
+            label,
+            row,
+            properties,
+        );
+    }
+
+    fn add_edge_properties(
+        &mut self,
+        [src_label, edge_label, dst_label]: [&str; 3],
+        row: &Row,
+        properties: &[Property],
+    ) {
+        if !self.options.properties {
+            return;
+        }
+        add_properties(
+            entry(entry(&mut self.edge_properties, src_label), edge_label),
+            self.options.approx,
+            dst_label,
+            row,
+            properties,
+        );
+    }
+
+    /// Counts an edge between the vertices given by their concrete label and id.
+    fn add_edge(
+        &mut self,
+        (src_label, src_id): (&str, u64),
+        edge_label: &str,
+        (dst_label, dst_id): (&str, u64),
+    ) {
+        let options = &self.options;
+        let counts = entry_with(
+            entry(entry(&mut self.edges, src_label), edge_label),
+            dst_label,
+            || {
+                let cycles = src_label == dst_label
+                    && options
+                        .cycles
+                        .iter()
+                        .any(|(label, edge)| label == src_label && edge == edge_label);
+                EdgeCounts::new(options.approx, options.paths, cycles)
+            },
+        );
+        counts.count += 1;
+        if let Some(pairs) = &mut counts.pairs {
+            pairs.push((src_id, dst_id));
+        }
+        match &mut counts.endpoints {
+            Endpoints::Exact {
+                out_degrees,
+                in_degrees,
+            } => {
+                *out_degrees.entry(src_id).or_insert(0) += 1;
+                *in_degrees.entry(dst_id).or_insert(0) += 1;
+            }
+            Endpoints::Approx(sketches) => {
+                let src_hash = hash_vertex(src_label, src_id);
+                let dst_hash = hash_vertex(dst_label, dst_id);
+                sketches.sources.insert(src_hash);
+                sketches.destinations.insert(dst_hash);
+                if let Some(sampled) = &mut counts.sampled {
+                    if src_hash.is_multiple_of(PATH_SAMPLE_RATE) {
+                        *sampled.out_degrees.entry(src_id).or_insert(0) += 1;
+                    }
+                    if dst_hash.is_multiple_of(PATH_SAMPLE_RATE) {
+                        *sampled.in_degrees.entry(dst_id).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Counts the paths of two distinct edges through every concrete label, in
+    /// all direction combinations, by multiplying the degrees of the vertices
+    /// in the middle. In approximate mode only the sampled vertices are visited
+    /// and their counts scaled up.
+    fn path2_cardinality(&self) -> Vec<([&str; 3], Pattern, f64)> {
+        let mut incident: HashMap<&str, Vec<Incident>> = HashMap::new();
+        for (src_label, edges) in &self.edges {
+            for (edge_label, dsts) in edges {
+                for (dst_label, counts) in dsts {
+                    let Some((out_degrees, in_degrees)) = counts.degrees() else {
+                        continue;
+                    };
+                    incident.entry(src_label).or_default().push(Incident {
+                        label: edge_label,
+                        direction: Direction::Outgoing,
+                        other: dst_label,
+                        degrees: out_degrees,
+                    });
+                    incident.entry(dst_label).or_default().push(Incident {
+                        label: edge_label,
+                        direction: Direction::Incoming,
+                        other: src_label,
+                        degrees: in_degrees,
+                    });
+                }
+            }
+        }
+
+        let scale = match self.options.approx {
+            true => PATH_SAMPLE_RATE,
+            false => 1,
+        };
+        let mut paths = Vec::new();
+        for (&middle, incident) in &incident {
+            for (i, first) in incident.iter().enumerate() {
+                for (j, second) in incident.iter().enumerate() {
+                    let (small, large) = match first.degrees.len() <= second.degrees.len() {
+                        true => (first.degrees, second.degrees),
+                        false => (second.degrees, first.degrees),
+                    };
+                    let count = small
+                        .iter()
+                        .filter_map(|(id, &degree)| {
+                            let other = *large.get(id)? as u64;
+                            // a path does not return over the edge it came from
+                            Some(degree as u64 * if i == j { other - 1 } else { other })
+                        })
+                        .sum::<u64>();
+                    if count == 0 {
+                        continue;
+                    }
+                    let pattern = Pattern {
+                        start: first.other.to_owned(),
+                        steps: vec![
+                            Step {
+                                label: first.label.to_owned(),
+                                direction: first.direction.reverse(),
+                                vertex: middle.to_owned(),
+                            },
+                            Step {
+                                label: second.label.to_owned(),
+                                direction: second.direction,
+                                vertex: second.other.to_owned(),
+                            },
+                        ],
+                    };
+                    paths.push((
+                        [first.other, middle, second.other],
+                        pattern,
+                        (count * scale) as f64,
+                    ));
+                }
+            }
+        }
+        paths
+    }
+
+    /// Tallies `err` if it concerns a single row and rows may be skipped.
+    fn skip_row(&mut self, on_error: OnError, path: &Path, err: Error) -> Result<()> {
+        match (on_error, err.skip_reason()) {
+            (OnError::Skip, Some(reason)) => {
+                *self
+                    .skipped_rows
+                    .entry(path.to_string_lossy().into_owned())
+                    .or_default()
+                    .entry(reason)
+                    .or_insert(0) += 1;
+                Ok(())
+            }
+            _ => Err(err),
+        }
+    }
+
+    fn merge(&mut self, other: Counts) {
+        for (label, count) in other.vertices {
+            *self.vertices.entry(label).or_insert(0) += count;
+        }
+        for (src_label, edges) in other.edges {
+            let src_entry = self.edges.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, counts) in dsts {
+                    match edge_entry.get_mut(&dst_label) {
+                        Some(entry) => entry.merge(counts),
+                        None => {
+                            edge_entry.insert(dst_label, counts);
+                        }
+                    }
+                }
+            }
+        }
+        for (label, series) in other.vertex_series {
+            merge_series(self.vertex_series.entry(label).or_default(), series);
+        }
+        for (src_label, edges) in other.edge_series {
+            let src_entry = self.edge_series.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, series) in dsts {
+                    merge_series(edge_entry.entry(dst_label).or_default(), series);
+                }
+            }
+        }
+        merge_properties(&mut self.vertex_properties, other.vertex_properties);
+        for (src_label, edges) in other.edge_properties {
+            let src_entry = self.edge_properties.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                merge_properties(src_entry.entry(edge_label).or_default(), dsts);
+            }
+        }
+        for (file, reasons) in other.skipped_rows {
+            let file_entry = self.skipped_rows.entry(file).or_default();
+            for (reason, count) in reasons {
+                *file_entry.entry(reason).or_insert(0) += count;
+            }
+        }
+    }
+
+    fn into_statistics(mut self, hierarchy: &LabelHierarchy, detail: Detail) -> Statistics {
+        let mut statistics = Statistics {
+            parent_labels: hierarchy.parents().clone(),
+            skipped_rows: std::mem::take(&mut self.skipped_rows),
+            ..Statistics::default()
+        };
+
+        for (label, count) in &self.vertices {
+            for key in hierarchy.ancestry(label).into_iter().chain([""]) {
+                *statistics
+                    .vertex_cardinality
+                    .entry(key.to_owned())
+                    .or_insert(0.0) += *count as f64;
+            }
+        }
+
+        for (label, series) in &self.vertex_series {
+            for key in hierarchy.ancestry(label).into_iter().chain([""]) {
+                let counts = series
+                    .iter()
+                    .map(|(bucket, &count)| (bucket.clone(), count as f64));
+                merge_series(entry(&mut statistics.vertex_series, key), counts);
+            }
+        }
+        for (src_label, edges) in &self.edge_series {
+            let src_keys = hierarchy.ancestry(src_label);
+            for (edge_label, dsts) in edges {
+                for (dst_label, series) in dsts {
+                    let dst_keys = hierarchy.ancestry(dst_label);
+                    for &src_key in src_keys.iter().chain([&""]) {
+                        for edge_key in [edge_label.as_str(), ""] {
+                            for &dst_key in dst_keys.iter().chain([&""]) {
+                                let counts = series
+                                    .iter()
+                                    .map(|(bucket, &count)| (bucket.clone(), count as f64));
+                                let entry = entry(
+                                    entry(entry(&mut statistics.edge_series, src_key), edge_key),
+                                    dst_key,
+                                );
+                                merge_series(entry, counts);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // count every edge under the ancestors of its endpoint labels as well
+        let mut rollups: HashMap<[&str; 3], Rollup> = HashMap::new();
+        for (src_label, edges) in &self.edges {
+            let src_keys = hierarchy.ancestry(src_label);
+            for (edge_label, dsts) in edges {
+                for (dst_label, counts) in dsts {
+                    let dst_keys = hierarchy.ancestry(dst_label);
+                    for &src_key in src_keys.iter().chain([&""]) {
+                        let src_entry = statistics
+                            .edge_cardinality
+                            .entry(src_key.to_owned())
+                            .or_default();
+                        for edge_key in [edge_label.as_str(), ""] {
+                            let edge_entry = src_entry.entry(edge_key.to_owned()).or_default();
+                            for &dst_key in dst_keys.iter().chain([&""]) {
+                                *edge_entry.entry(dst_key.to_owned()).or_insert(0.0) +=
+                                    counts.count as f64;
+
+                                rollups
+                                    .entry([src_key, edge_key, dst_key])
+                                    .or_default()
+                                    .push((src_label, dst_label, &counts.endpoints));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // one key at a time, as the degrees of the wildcard keys cover most
+        // vertices of the graph
+        for (key, rollup) in rollups {
+            let distinct = match approx_endpoints(&rollup) {
+                Some(sketches) => {
+                    let distinct = sketches.distinct();
+                    insert_triple(&mut statistics.sketches, key, sketches);
+                    distinct
+                }
+                None => {
+                    let out_degrees = rolled_up_degrees(&rollup, Direction::Outgoing);
+                    let in_degrees = rolled_up_degrees(&rollup, Direction::Incoming);
+                    // every endpoint has a degree
+                    let distinct = DistinctEndpoints {
+                        sources: out_degrees.len() as f64,
+                        destinations: in_degrees.len() as f64,
+                    };
+                    let distribution = DegreeDistribution {
+                        out_degree: Distribution::from_degrees(out_degrees),
+                        in_degree: Distribution::from_degrees(in_degrees),
+                    };
+                    insert_triple(&mut statistics.degree_distribution, key, distribution);
+                    distinct
+                }
+            };
+            insert_triple(&mut statistics.distinct_endpoints, key, distinct);
+        }
+
+        if self.options.paths {
+            // count every path under the ancestors of its vertex labels as well
+            for (vertex_labels, mut pattern, count) in self.path2_cardinality() {
+                let [start, middle, end] = vertex_labels.map(|label| hierarchy.ancestry(label));
+                for &start_key in &start {
+                    for &middle_key in &middle {
+                        for &end_key in &end {
+                            pattern.start = start_key.to_owned();
+                            pattern.steps[0].vertex = middle_key.to_owned();
+                            pattern.steps[1].vertex = end_key.to_owned();
+                            *statistics
+                                .path2_cardinality
+                                .entry(pattern.to_string())
+                                .or_insert(0.0) += count;
+                        }
+                    }
+                }
+            }
+        }
+
+        if !self.options.approx {
+            for (src_label, edges) in &self.edges {
+                let sets = characteristic_sets(edges);
+                for key in hierarchy.ancestry(src_label) {
+                    merge_characteristic_sets(
+                        statistics
+                            .characteristic_sets
+                            .entry(key.to_owned())
+                            .or_default(),
+                        &sets,
+                    );
+                }
+            }
+        }
+
+        for (src_label, edges) in &self.edges {
+            for (edge_label, dsts) in edges {
+                for (dst_label, counts) in dsts {
+                    if let Some(pairs) = &counts.pairs {
+                        let key = [src_label.as_str(), edge_label, dst_label];
+                        insert_triple(&mut statistics.cycles, key, cycle_statistics(pairs));
+                    }
+                }
+            }
+        }
+
+        // the property counts are consumed as they are rolled up, so that the
+        // value hashes of a column are not held twice
+        let mut vertex_properties: HashMap<String, HashMap<String, ColumnCounts>> = HashMap::new();
+        for (label, columns) in std::mem::take(&mut self.vertex_properties) {
+            let keys = owned(hierarchy.ancestry(&label));
+            roll_up_columns(&mut vertex_properties, keys, columns);
+        }
+        statistics.property_statistics.vertices = vertex_properties
+            .into_iter()
+            .map(|(label, columns)| (label, finish_properties(columns, detail)))
+            .collect();
+
+        let mut edge_properties: HashMap<[String; 3], HashMap<String, ColumnCounts>> =
+            HashMap::new();
+        for (src_label, edges) in std::mem::take(&mut self.edge_properties) {
+            let src_keys = owned(hierarchy.ancestry(&src_label));
+            for (edge_label, dsts) in edges {
+                for (dst_label, columns) in dsts {
+                    let dst_keys = owned(hierarchy.ancestry(&dst_label));
+                    let mut keys = Vec::new();
+                    for src_key in &src_keys {
+                        for dst_key in &dst_keys {
+                            keys.push([src_key.clone(), edge_label.clone(), dst_key.clone()]);
+                        }
+                    }
+                    roll_up_columns(&mut edge_properties, keys, columns);
+                }
+            }
+        }
+        for ([src_key, edge_label, dst_key], columns) in edge_properties {
+            let columns = finish_properties(columns, detail);
+            let key = [src_key.as_str(), &edge_label, &dst_key];
+            insert_triple(&mut statistics.property_statistics.edges, key, columns);
+        }
+
+        statistics
+    }
+}
+
+/// Merges the endpoint sketches of a rolled-up triple, or returns `None` if
+/// its endpoints are tracked exactly.
+fn approx_endpoints(rollup: &Rollup) -> Option<EndpointSketches> {
+    let mut merged = None;
+    for (_, _, endpoints) in rollup {
+        if let Endpoints::Approx(sketches) = endpoints {
+            merged
+                .get_or_insert_with(EndpointSketches::default)
+                .merge(sketches);
+        }
+    }
+    merged
+}
+
+/// A foreign-key edge whose referenced vertex may not have been registered yet.
+#[derive(Debug)]
+struct PendingEdge {
+    path: PathBuf,
+    position: Position,
+    column: String,
+    /// Concrete label and id of the referencing vertex.
+    label: String,
+    own_id: u64,
+    foreign_key: ForeignKey,
+    /// Id of the referenced vertex.
+    id: u64,
+    /// Time series bucket of the referencing row.
+    bucket: Option<String>,
+}
+
+/// A non-empty foreign-key column of a vertex row.
+struct Reference<'a> {
+    index: usize,
+    foreign_key: &'a ForeignKey,
+    id: u64,
+}
+
+/// Parses the id, the concrete label and the foreign keys of a vertex row.
+fn parse_vertex<'a>(
+    row: &Row,
+    columns: &'a VertexColumns,
+    mapping: &VertexMapping,
+) -> Result<(u64, String, Vec<Reference<'a>>)> {
+    let id = row.id(columns.id)?;
+    let label = match columns.label {
+        Some(label_index) => normalise_label(columns.dialect, row.field(label_index)?),
+        None => mapping.label.clone(),
+    };
+
+    let mut references = Vec::with_capacity(columns.foreign_keys.len());
+    for (index, foreign_key) in &columns.foreign_keys {
+        if row.field(*index)?.is_empty() {
+            continue;
+        }
+        references.push(Reference {
+            index: *index,
+            foreign_key,
+            id: row.id(*index)?,
+        });
+    }
+
+    Ok((id, label, references))
+}
+
+/// Resolves the concrete label of the vertex referenced by column `index` of
+/// `row`, and returns it with the vertex id.
+fn resolve_endpoint(
+    hierarchy: &LabelHierarchy,
+    row: &Row,
+    index: usize,
+    label: &str,
+) -> Result<(String, u64)> {
+    let id = row.id(index)?;
+    hierarchy
+        .resolve(label, id)
+        .map(|resolved| (resolved.to_owned(), id))
+        .ok_or_else(|| Error::UnknownEndpoint {
+            path: row.path.to_owned(),
+            position: row.position(),
+            column: row.column(index),
+            id,
+            label: label.to_owned(),
+        })
+}
+
+/// Vertex files with a label column, keyed by their parent label.
+type LabelledFiles = BTreeMap<String, Vec<(PathBuf, VertexMapping)>>;
+
+/// Splits off the vertex files with a label column, grouped by parent label,
+/// from the rest.
+fn split_labelled(
+    files: Vec<(PathBuf, Entity)>,
+) -> Result<(LabelledFiles, Vec<(PathBuf, Entity)>)> {
+    let mut labelled = LabelledFiles::new();
+    let mut rest = Vec::new();
+    for (path, entity) in files {
+        match entity {
+            Entity::Vertex(mapping) => {
+                let header = read_header(&path)?;
+                let columns = VertexColumns::detect(&path, &header, &mapping)?;
+                if columns.label.is_some() {
+                    labelled
+                        .entry(mapping.label.clone())
+                        .or_default()
+                        .push((path, mapping));
+                } else {
+                    rest.push((path, Entity::Vertex(mapping)));
+                }
+            }
+            Entity::Edge(_) => rest.push((path, entity)),
+        }
+    }
+    Ok((labelled, rest))
+}
+
+/// Imports the vertex files that register the concrete labels of one parent
+/// label, one after another. Their foreign-key edges may reference vertices of
+/// other such files and are returned for `Context::resolve_pending`.
+fn import_labelled(
+    files: Vec<(PathBuf, VertexMapping)>,
+    on_error: OnError,
+    reporter: Reporter,
+    mut counts: Counts,
+) -> Result<(LabelHierarchy, Counts, Vec<PendingEdge>)> {
+    let mut hierarchy = LabelHierarchy::default();
+    let mut pending = Vec::new();
+
+    for (path, mapping) in files {
+        reporter.report(Progress::Import(&path));
+        let Source { header, records } = Source::open(&path)?;
+        let columns = VertexColumns::detect(&path, &header, &mapping)?;
+
+        for record in records {
+            let (position, record) = record?;
+            let row = Row {
+                path: &path,
+                header: &header,
+                position,
+                record,
+            };
+
+            let parsed =
+                parse_vertex(&row, &columns, &mapping).and_then(|(id, label, references)| {
+                    if !hierarchy.insert(&mapping.label, id, &label) {
+                        return Err(Error::DuplicateId {
+                            path: path.clone(),
+                            position: row.position(),
+                            column: row.column(columns.id),
+                            id,
+                            label: mapping.label.clone(),
+                        });
+                    }
+                    Ok((id, label, references))
+                });
+            let (id, label, references) = match parsed {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    counts.skip_row(on_error, &path, err)?;
+                    continue;
+                }
+            };
+
+            counts.add_vertex(&label);
+            counts.add_vertex_properties(&label, &row, &columns.properties);
+            let bucket = counts.bucket(&row, &columns.properties);
+            if let Some(bucket) = &bucket {
+                counts.add_vertex_bucket(&label, bucket);
+            }
+            for reference in references {
+                pending.push(PendingEdge {
+                    path: path.clone(),
+                    position: row.position(),
+                    column: row.column(reference.index),
+                    label: label.clone(),
+                    own_id: id,
+                    foreign_key: reference.foreign_key.clone(),
+                    id: reference.id,
+                    bucket: bucket.clone(),
+                });
+            }
+        }
+    }
+
+    Ok((hierarchy, counts, pending))
+}
+
+enum Task {
+    Vertex(VertexColumns, VertexMapping),
+    Edge(EdgeColumns, EdgeMapping),
+}
+
+/// An input file whose records are imported by the worker pool.
+struct FileJob {
+    path: PathBuf,
+    header: csv::StringRecord,
+    task: Task,
+}
+
+struct Batch {
+    file: Arc<FileJob>,
+    records: Vec<Record>,
+}
+
+/// Reads the file at `path` and hands its records to the worker pool in batches.
+fn read_batches(
+    path: PathBuf,
+    entity: Entity,
+    tx: &SyncSender<Batch>,
+    failed: &AtomicBool,
+    reporter: &Reporter,
+) -> Result<()> {
+    reporter.report(Progress::Import(&path));
+    let Source { header, records } = Source::open(&path)?;
+    let task = match entity {
+        Entity::Vertex(mapping) => {
+            Task::Vertex(VertexColumns::detect(&path, &header, &mapping)?, mapping)
+        }
+        Entity::Edge(mapping) => Task::Edge(EdgeColumns::detect(&path, &header)?, mapping),
+    };
+    let file = Arc::new(FileJob { path, header, task });
+
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for record in records {
+        batch.push(record?);
+        if batch.len() == BATCH_SIZE {
+            let records = std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE));
+            // stop early if another file failed, or all workers are gone
+            if failed.load(Ordering::Relaxed)
+                || tx
+                    .send(Batch {
+                        file: file.clone(),
+                        records,
+                    })
+                    .is_err()
+            {
+                return Ok(());
+            }
+        }
+    }
+    if !batch.is_empty() {
+        let _ = tx.send(Batch {
+            file,
+            records: batch,
+        });
+    }
+
+    Ok(())
+}
+
+/// Imports batches of records into its own partial counts.
+struct Worker {
+    hierarchy: Arc<LabelHierarchy>,
+    on_error: OnError,
+    counts: Counts,
+}
+
+impl Worker {
+    fn import(&mut self, batch: Batch) -> Result<()> {
+        let file = &batch.file;
+        for (position, record) in batch.records {
+            let row = Row {
+                path: &file.path,
+                header: &file.header,
+                position,
+                record,
+            };
+            let imported = match &file.task {
+                Task::Vertex(columns, mapping) => self.import_vertex(&row, columns, mapping),
+                Task::Edge(columns, mapping) => self.import_edge(&row, columns, mapping),
+            };
+            if let Err(err) = imported {
+                self.counts.skip_row(self.on_error, &file.path, err)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn import_vertex(
+        &mut self,
+        row: &Row,
+        columns: &VertexColumns,
+        mapping: &VertexMapping,
+    ) -> Result<()> {
+        let (id, label, references) = parse_vertex(row, columns, mapping)?;
+        self.counts.add_vertex(&label);
+        self.counts
+            .add_vertex_properties(&label, row, &columns.properties);
+        // foreign-key edges are bucketed by the creationDate of their vertex
+        let bucket = self.counts.bucket(row, &columns.properties);
+        if let Some(bucket) = &bucket {
+            self.counts.add_vertex_bucket(&label, bucket);
+        }
+        for reference in references {
+            let foreign_key = reference.foreign_key;
+            // like in `Context::resolve_pending`, an unknown foreign key only
+            // leaves out the edge
+            let referenced = match self.hierarchy.resolve(&foreign_key.dst, reference.id) {
+                Some(referenced) => referenced,
+                None => {
+                    let err = Error::UnknownForeignKey {
+                        path: row.path.to_owned(),
+                        position: row.position(),
+                        column: row.column(reference.index),
+                        id: reference.id,
+                        label: foreign_key.dst.clone(),
+                    };
+                    self.counts.skip_row(self.on_error, row.path, err)?;
+                    continue;
+                }
+            };
+            let (src, dst) =
+                foreign_key.endpoints((label.as_str(), id), (referenced, reference.id));
+            self.counts.add_edge(src, &foreign_key.label, dst);
+            if let Some(bucket) = &bucket {
+                self.counts
+                    .add_edge_bucket([src.0, &foreign_key.label, dst.0], bucket);
+            }
+        }
+        Ok(())
+    }
+
+    fn import_edge(
+        &mut self,
+        row: &Row,
+        columns: &EdgeColumns,
+        mapping: &EdgeMapping,
+    ) -> Result<()> {
+        let (src_label, src_id) =
+            resolve_endpoint(&self.hierarchy, row, columns.start, &mapping.src)?;
+        let (dst_label, dst_id) =
+            resolve_endpoint(&self.hierarchy, row, columns.end, &mapping.dst)?;
+        self.counts
+            .add_edge((&src_label, src_id), &mapping.label, (&dst_label, dst_id));
+        let triple = [src_label.as_str(), &mapping.label, &dst_label];
+        self.counts
+            .add_edge_properties(triple, row, &columns.properties);
+        if let Some(bucket) = self.counts.bucket(row, &columns.properties) {
+            self.counts.add_edge_bucket(triple, &bucket);
+        }
+        Ok(())
+    }
+}
+
+/// Awaits a blocking task, resuming its panic if it panicked.
+async fn join<T>(handle: JoinHandle<T>) -> T {
+    match handle.await {
+        Ok(value) => value,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Imports the files of a dataset and counts their vertices and edges.
+///
+/// ```no_run
+/// # async fn example() -> ldbc_stat_gen::Result<()> {
+/// use ldbc_stat_gen::{Context, OnError};
+///
+/// let mut context = Context::builder().on_error(OnError::Skip).jobs(4).build();
+/// context.import_dir("social_network".as_ref()).await?;
+/// context.into_statistics().save("statistics.json".as_ref())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Context {
+    schema: Schema,
+    on_error: OnError,
+    jobs: usize,
+    detail: Detail,
+    reporter: Reporter,
+
+    hierarchy: LabelHierarchy,
+    counts: Counts,
+}
+
+/// Configures a `Context`; unset options default to the built-in LDBC SNB
+/// schema, `OnError::Fail`, one worker per CPU, exact counting without
+/// property statistics, histograms of 32 buckets and the 10 most common values.
+#[derive(Debug, Default)]
+pub struct ContextBuilder {
+    schema: Option<Schema>,
+    on_error: OnError,
+    jobs: Option<usize>,
+    approx: bool,
+    paths: bool,
+    cycles: Vec<(String, String)>,
+    time_series: Option<Granularity>,
+    properties: bool,
+    histogram_buckets: Option<usize>,
+    most_common: Option<usize>,
+    reporter: Reporter,
+}
+
+impl ContextBuilder {
+    pub fn schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub fn on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Sets the number of import workers.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Estimates distinct counts with HyperLogLog sketches instead of tracking
+    /// every vertex, which bounds memory use but leaves out degree
+    /// distributions.
+    pub fn approx(mut self, approx: bool) -> Self {
+        self.approx = approx;
+        self
+    }
+
+    /// Counts the paths of two edges for `Statistics::path2_cardinality`. In
+    /// approximate mode they are estimated from a sample of 1/64th of the
+    /// vertices.
+    pub fn paths(mut self, paths: bool) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// Counts triangles and 4-cycles among the vertices of concrete label
+    /// `label` connected by `edge_label` edges, for `Statistics::cycles`. Their
+    /// edges are kept in memory until the end of the import.
+    pub fn cycles(mut self, label: &str, edge_label: &str) -> Self {
+        self.cycles.push((label.to_owned(), edge_label.to_owned()));
+        self
+    }
+
+    /// Counts vertices and edges with a `creationDate` column per day, month
+    /// or year, for `Statistics::vertex_series` and `edge_series`.
+    pub fn time_series(mut self, granularity: Granularity) -> Self {
+        self.time_series = Some(granularity);
+        self
+    }
+
+    /// Collects statistics of the property columns of vertex and edge files,
+    /// for `Statistics::property_statistics`. In exact mode the hashes of the
+    /// distinct values of every column are kept until the end of the import.
+    pub fn properties(mut self, properties: bool) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Sets the number of buckets of the histograms of numeric and temporal
+    /// properties; 0 leaves them out.
+    pub fn histogram_buckets(mut self, buckets: usize) -> Self {
+        self.histogram_buckets = Some(buckets);
+        self
+    }
+
+    /// Sets how many of the most common values of each string property are
+    /// listed; 0 leaves them out. `k` is capped at `MAX_MOST_COMMON`.
+    pub fn most_common(mut self, k: usize) -> Self {
+        self.most_common = Some(k.min(MAX_MOST_COMMON));
+        self
+    }
+
+    /// Calls `progress` with each file as it is imported or ignored, e.g. to
+    /// log the progress of a long import. Nothing is reported by default.
+    pub fn progress(mut self, progress: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.reporter = Reporter(Some(Arc::new(progress)));
+        self
+    }
+
+    pub fn build(self) -> Context {
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, NonZeroUsize::get));
+        Context {
+            schema: self.schema.unwrap_or_default(),
+            on_error: self.on_error,
+            jobs: jobs.max(1),
+            detail: Detail {
+                histogram_buckets: self.histogram_buckets.unwrap_or(HISTOGRAM_BUCKETS),
+                most_common: self.most_common.unwrap_or(MOST_COMMON),
+            },
+            reporter: self.reporter,
+            hierarchy: LabelHierarchy::default(),
+            counts: Counts::new(Arc::new(Options {
+                approx: self.approx,
+                paths: self.paths,
+                cycles: self.cycles,
+                time_series: self.time_series,
+                properties: self.properties,
+            })),
+        }
+    }
+}
+
+impl Context {
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::default()
+    }
+
+    /// Imports the data files below `csv_dir` that the schema maps to labels.
+    pub async fn import_dir(&mut self, csv_dir: &Path) -> Result<()> {
+        let files = self.resolve_files(discover(csv_dir)?)?;
+        self.import(files).await
+    }
+
+    /// Imports the files of the insert or delete batches of the BI workload in
+    /// `dir`, such as `inserts` next to `initial_snapshot`, or only those of
+    /// `batch` if given.
+    ///
+    /// Delete batches are counted like inserts, as the vertices and edges to
+    /// remove with `Statistics::remove`. Endpoints in the initial snapshot
+    /// resolve to their concrete labels once `register_labels` read it.
+    pub async fn import_batches(&mut self, dir: &Path, batch: Option<&str>) -> Result<()> {
+        let files = self.resolve_files(discover_batches(dir, batch)?)?;
+        self.import(files).await
+    }
+
+    /// Maps `files` to their entities, leaving out those the schema ignores.
+    fn resolve_files(&self, files: Vec<InputFile>) -> Result<Vec<(PathBuf, Entity)>> {
+        let mut resolved = Vec::new();
+        for file in files {
+            match self.schema.resolve(&file)? {
+                Some(entity) => resolved.push((file.path, entity)),
+                None => self.reporter.report(Progress::Ignore(&file.path)),
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Imports `files` concurrently.
+    ///
+    /// Vertex files with a label column are imported first, since edges resolve
+    /// their endpoints through the concrete labels they register. Those of
+    /// different parent labels are imported in parallel. All remaining files are
+    /// then split into batches of records which a pool of workers imports into
+    /// partial counts, merged at the end.
+    pub async fn import(&mut self, files: Vec<(PathBuf, Entity)>) -> Result<()> {
+        let (labelled, rest) = split_labelled(files)?;
+        let (counts, pending) = self.import_all_labelled(labelled).await?;
+        self.counts.merge(counts);
+        self.resolve_pending(pending)?;
+
+        self.import_batched(rest).await
+    }
+
+    /// Registers the concrete labels of the vertices below `csv_dir` read from
+    /// a label column, without counting anything, so that update batches can
+    /// resolve the endpoints they reference in the initial snapshot.
+    pub async fn register_labels(&mut self, csv_dir: &Path) -> Result<()> {
+        let files = self.resolve_files(discover(csv_dir)?)?;
+        let (labelled, _) = split_labelled(files)?;
+        self.import_all_labelled(labelled).await?;
+        Ok(())
+    }
+
+    /// Imports the vertex files of each parent label in parallel, registering
+    /// their concrete labels. Returns their counts and foreign-key edges.
+    async fn import_all_labelled(
+        &mut self,
+        labelled: LabelledFiles,
+    ) -> Result<(Counts, Vec<PendingEdge>)> {
+        let handles = labelled
+            .into_values()
+            .map(|files| {
+                let (on_error, reporter) = (self.on_error, self.reporter.clone());
+                let counts = self.counts.empty();
+                tokio::task::spawn_blocking(move || {
+                    import_labelled(files, on_error, reporter, counts)
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut all_counts = self.counts.empty();
+        let mut pending = Vec::new();
+        let mut result = Ok(());
+        for handle in handles {
+            match join(handle).await {
+                Ok((hierarchy, counts, edges)) => {
+                    self.hierarchy.extend(hierarchy);
+                    all_counts.merge(counts);
+                    pending.extend(edges);
+                }
+                Err(err) => result = result.and(Err(err)),
+            }
+        }
+        result.map(|()| (all_counts, pending))
+    }
+
+    /// Counts the foreign-key edges deferred by `import_labelled`.
+    fn resolve_pending(&mut self, pending: Vec<PendingEdge>) -> Result<()> {
+        for pending in pending {
+            let foreign_key = &pending.foreign_key;
+            match self.hierarchy.resolve(&foreign_key.dst, pending.id) {
+                Some(referenced) => {
+                    let (src, dst) = foreign_key.endpoints(
+                        (pending.label.as_str(), pending.own_id),
+                        (referenced, pending.id),
+                    );
+                    self.counts.add_edge(src, &foreign_key.label, dst);
+                    if let Some(bucket) = &pending.bucket {
+                        self.counts
+                            .add_edge_bucket([src.0, &foreign_key.label, dst.0], bucket);
+                    }
+                }
+                None => {
+                    let err = Error::UnknownForeignKey {
+                        path: pending.path.clone(),
+                        position: pending.position,
+                        column: pending.column,
+                        id: pending.id,
+                        label: foreign_key.dst.clone(),
+                    };
+                    self.counts.skip_row(self.on_error, &pending.path, err)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn import_batched(&mut self, files: Vec<(PathBuf, Entity)>) -> Result<()> {
+        let hierarchy = Arc::new(std::mem::take(&mut self.hierarchy));
+        let failed = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = sync_channel::<Batch>(self.jobs * 2);
+        let rx = Arc::new(Mutex::new(rx));
+
+        let workers = (0..self.jobs)
+            .map(|_| {
+                let mut worker = Worker {
+                    hierarchy: hierarchy.clone(),
+                    on_error: self.on_error,
+                    counts: self.counts.empty(),
+                };
+                let (rx, failed) = (rx.clone(), failed.clone());
+                tokio::task::spawn_blocking(move || {
+                    loop {
+                        let batch = match rx.lock() {
+                            Ok(rx) => rx.recv(),
+                            Err(_) => break,
+                        };
+                        let batch = match batch {
+                            Ok(batch) => batch,
+                            Err(_) => break,
+                        };
+                        if let Err(err) = worker.import(batch) {
+                            failed.store(true, Ordering::Relaxed);
+                            return Err(err);
+                        }
+                    }
+                    Ok(worker.counts)
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(rx);
+
+        // bound the number of files read at the same time
+        let permits = Arc::new(Semaphore::new(self.jobs));
+        let mut readers = Vec::new();
+        for (path, entity) in files {
+            let permit = permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            let (tx, failed) = (tx.clone(), failed.clone());
+            let reporter = self.reporter.clone();
+            readers.push(tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                let result = read_batches(path, entity, &tx, &failed, &reporter);
+                if result.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                result
+            }));
+        }
+        drop(tx);
+
+        let mut result = Ok(());
+        for reader in readers {
+            result = result.and(join(reader).await);
+        }
+        for worker in workers {
+            match join(worker).await {
+                Ok(counts) => self.counts.merge(counts),
+                Err(err) => result = result.and(Err(err)),
+            }
+        }
+
+        self.hierarchy =
+            Arc::try_unwrap(hierarchy).unwrap_or_else(|hierarchy| (*hierarchy).clone());
+        result
+    }
+
+    pub fn into_statistics(self) -> Statistics {
+        self.counts.into_statistics(&self.hierarchy, self.detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::SkipReason, testing::TempDir};
+
+    async fn count(dir: &TempDir, builder: ContextBuilder) -> Result<Statistics> {
+        let mut context = builder.build();
+        context.import_dir(dir.path()).await?;
+        Ok(context.into_statistics())
+    }
+
+    fn triple<V: Copy>(
+        map: &HashMap<String, HashMap<String, HashMap<String, V>>>,
+        [src_label, edge_label, dst_label]: [&str; 3],
+    ) -> Option<V> {
+        map.get(src_label)?.get(edge_label)?.get(dst_label).copied()
+    }
+
+    /// Writes a small Spark-datagen style dataset whose labelled place file is
+    /// split into several parts, referencing places of later parts, and whose
+    /// edges span several batches.
+    fn write_dataset(dir: &TempDir) {
+        let mut places = vec![String::from("id|name|type|PartOfPlaceId")];
+        places.push("0|Asia|Continent|".to_owned());
+        for country in 1..=10 {
+            places.push(format!("{}|c{}|Country|0", country, country));
+        }
+        for city in 11..=100 {
+            places.push(format!("{}|x{}|City|{}", city, city, city % 10 + 1));
+        }
+        // the countries are read after the cities referencing them
+        let parts = [&places[21..], &places[1..11], &places[11..21]];
+        for (i, part) in parts.iter().enumerate() {
+            let rows = std::iter::once(&places[0]).chain(*part);
+            let contents = rows.map(|row| format!("{}\n", row)).collect::<String>();
+            dir.write(&format!("static/Place/part-{}.csv", i), contents);
+        }
+
+        let mut organisations = String::from("id|type|name|LocationPlaceId\n");
+        for id in 0..20 {
+            let kind = ["Company", "University"][id % 2];
+            organisations += &format!("{}|{}|o{}|{}\n", id, kind, id, id % 10 + 1);
+        }
+        dir.write("static/Organisation/part-0.csv", organisations);
+
+        for part in 0..2 {
+            let mut persons = String::from("creationDate|id|firstName|LocationCityId\n");
+            for id in (part * 1500)..(part + 1) * 1500 {
+                let date = format!("2010-{:02}-01T00:00:00.000+00:00", id % 12 + 1);
+                persons += &format!("{}|{}|p{}|{}\n", date, id, id % 97, id % 90 + 11);
+            }
+            dir.write(&format!("dynamic/Person/part-{}.csv", part), persons);
+        }
+        let mut knows = String::from("creationDate|Person1Id|Person2Id\n");
+        for i in 0..10_000u64 {
+            let (a, b) = (i % 3000, (i * 7919 + 13) % 3000);
+            knows += &format!("2011-{:02}-02T00:00:00.000+00:00|{}|{}\n", i % 12 + 1, a, b);
+        }
+        dir.write("dynamic/Person_knows_Person/part-0.csv", knows);
+        let mut study_at = String::from("creationDate|PersonId|UniversityId|classYear\n");
+        for person in (0..3000).step_by(3) {
+            let university = person % 10 * 2 + 1;
+            study_at += &format!(
+                "2012-01-01T00:00:00.000+00:00|{}|{}|2005\n",
+                person, university
+            );
+        }
+        dir.write("dynamic/Person_studyAt_University/part-0.csv", study_at);
+    }
+
+    #[tokio::test]
+    async fn imports_the_same_with_any_number_of_jobs() {
+        let dir = TempDir::new("jobs");
+        write_dataset(&dir);
+        let builder = |jobs| {
+            Context::builder()
+                .jobs(jobs)
+                .paths(true)
+                .cycles("Person", "KNOWS")
+                .time_series(Granularity::Month)
+                .properties(true)
+        };
+
+        let expected = count(&dir, builder(1)).await.unwrap();
+        assert_eq!(expected.vertex_cardinality["City"], 90.0);
+        assert_eq!(expected.vertex_cardinality["Place"], 101.0);
+        let is_part_of = ["City", "IS_PART_OF", "Country"];
+        assert_eq!(triple(&expected.edge_cardinality, is_part_of), Some(90.0));
+        let study_at = ["Person", "STUDY_AT", "University"];
+        assert_eq!(triple(&expected.edge_cardinality, study_at), Some(1000.0));
+        let study_at = ["Person", "STUDY_AT", "Organisation"];
+        assert_eq!(triple(&expected.edge_cardinality, study_at), Some(1000.0));
+        assert!(!expected.path2_cardinality.is_empty());
+        assert!(!expected.cycles.is_empty());
+        assert!(!expected.edge_series.is_empty());
+        assert!(!expected.property_statistics.is_empty());
+        for jobs in [2, 3, 8] {
+            assert_eq!(count(&dir, builder(jobs)).await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn collects_property_statistics_only_on_request() {
+        let dir = TempDir::new("properties");
+        dir.write("static/tag_0_0.csv", "id|name\n0|Go\n1|Rust\n");
+        dir.write("dynamic/person_0_0.csv", "id|firstName\n10|Ana\n");
+
+        let statistics = count(&dir, Context::builder()).await.unwrap();
+        assert!(statistics.property_statistics.is_empty());
+
+        let builder = Context::builder().properties(true);
+        let statistics = count(&dir, builder).await.unwrap();
+        let names = &statistics.property_statistics.vertices["Tag"]["name"];
+        assert_eq!((names.count, names.distinct), (2, 2.0));
+    }
+
+    #[tokio::test]
+    async fn reports_progress_to_the_callback() {
+        let dir = TempDir::new("progress");
+        let tag = dir.write("static/tag_0_0.csv", "id|name\n0|Go\n");
+        let person = dir.write("dynamic/person_0_0.csv", "id|firstName\n10|Ana\n");
+        let email = dir.write(
+            "dynamic/person_email_emailaddress_0_0.csv",
+            "Person.id|email\n",
+        );
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let builder = Context::builder().progress({
+            let events = events.clone();
+            move |progress| {
+                let event = match progress {
+                    Progress::Import(path) => ("import", path.to_owned()),
+                    Progress::Ignore(path) => ("ignore", path.to_owned()),
+                };
+                events.lock().unwrap().push(event);
+            }
+        });
+        count(&dir, builder).await.unwrap();
+        let mut events = events.lock().unwrap().clone();
+        events.sort();
+        let mut expected = vec![("ignore", email), ("import", person), ("import", tag)];
+        expected.sort();
+        assert_eq!(events, expected);
+    }
+
+    #[tokio::test]
+    async fn counts_vertices_with_unknown_foreign_keys_in_lenient_mode() {
+        let dir = TempDir::new("unknown_foreign_keys");
+        // a labelled file, whose foreign keys are resolved after all of its
+        // vertices are known, and one resolved row by row
+        dir.write(
+            "static/place_0_0.csv",
+            "id|name|type|isPartOf\n0|Asia|continent|\n1|India|country|0\n\
+             2|Delhi|city|1\n3|Mumbai|city|999\n",
+        );
+        dir.write(
+            "dynamic/person_0_0.csv",
+            "id|firstName|place\n10|Ana|2\n11|Ben|999\n",
+        );
+
+        let builder = Context::builder().on_error(OnError::Skip).jobs(1);
+        let statistics = count(&dir, builder).await.unwrap();
+        assert_eq!(statistics.vertex_cardinality["City"], 2.0);
+        assert_eq!(statistics.vertex_cardinality["Person"], 2.0);
+        let is_part_of = ["City", "IS_PART_OF", "Country"];
+        assert_eq!(triple(&statistics.edge_cardinality, is_part_of), Some(1.0));
+        let is_located_in = ["Person", "IS_LOCATED_IN", "City"];
+        assert_eq!(
+            triple(&statistics.edge_cardinality, is_located_in),
+            Some(1.0)
+        );
+        for file in ["static/place_0_0.csv", "dynamic/person_0_0.csv"] {
+            let file = dir.path().join(file).to_string_lossy().into_owned();
+            let reasons = &statistics.skipped_rows[&file];
+            assert_eq!(reasons.len(), 1);
+            assert_eq!(reasons[&SkipReason::UnknownForeignKey], 1);
+        }
+
+        let err = count(&dir, Context::builder().jobs(1)).await.unwrap_err();
+        assert_eq!(err.skip_reason(), Some(SkipReason::UnknownForeignKey));
+    }
+
+    #[tokio::test]
+    async fn tallies_skipped_rows_per_file_and_reason() {
+        let dir = TempDir::new("skipped_rows");
+        let place = dir.write(
+            "static/place_0_0.csv",
+            "id|name|type|isPartOf\n0|Asia|continent|\n1|India|country|0\n\
+             1|Italy|country|0\nx|Delhi|city|1\n2|Mumbai\n3|Pune|city|999\n",
+        );
+        dir.write(
+            "dynamic/person_0_0.csv",
+            "id|firstName|place\n10|Ana|3\n11|Ben|3\n",
+        );
+        let knows = dir.write(
+            "dynamic/person_knows_person_0_0.csv",
+            "Person.id|Person.id|creationDate\n\
+             10|11|2010-01-01T00:00:00.000+0000\n\
+             11\n\
+             y|10|2010-01-01T00:00:00.000+0000\n",
+        );
+        // endpoints are looked up in labelled files only
+        let is_located_in = dir.write(
+            "dynamic/person_isLocatedIn_place_0_0.csv",
+            "Person.id|Place.id\n10|3\n11|99\n",
+        );
+
+        let builder = Context::builder().on_error(OnError::Skip);
+        let statistics = count(&dir, builder).await.unwrap();
+        assert_eq!(statistics.vertex_cardinality["Place"], 3.0);
+        assert_eq!(statistics.vertex_cardinality["Country"], 1.0);
+        assert_eq!(statistics.vertex_cardinality["City"], 1.0);
+        assert_eq!(statistics.vertex_cardinality["Person"], 2.0);
+        let knows_triple = ["Person", "KNOWS", "Person"];
+        assert_eq!(
+            triple(&statistics.edge_cardinality, knows_triple),
+            Some(1.0)
+        );
+
+        let tally = |path: &Path, reasons: &[SkipReason]| {
+            let path = path.to_string_lossy().into_owned();
+            let reasons = reasons.iter().map(|&reason| (reason, 1)).collect();
+            (path, reasons)
+        };
+        let expected = SkippedRows::from([
+            tally(
+                &place,
+                &[
+                    SkipReason::ShortRow,
+                    SkipReason::InvalidId,
+                    SkipReason::DuplicateId,
+                    SkipReason::UnknownForeignKey,
+                ],
+            ),
+            tally(&knows, &[SkipReason::ShortRow, SkipReason::InvalidId]),
+            tally(&is_located_in, &[SkipReason::UnknownEndpoint]),
+        ]);
+        assert_eq!(statistics.skipped_rows, expected);
+    }
+
+    #[tokio::test]
+    async fn counts_paths_of_two_distinct_edges() {
+        let dir = TempDir::new("paths");
+        dir.write(
+            "static/place_0_0.csv",
+            "id|name|type|isPartOf\n0|India|country|\n1|Delhi|city|0\n2|Mumbai|city|0\n",
+        );
+        dir.write(
+            "dynamic/person_0_0.csv",
+            "id|firstName|place\n10|Ana|1\n11|Ben|1\n12|Cem|2\n",
+        );
+        // 10 -> 11 -> 12 and 10 -> 12
+        dir.write(
+            "dynamic/person_knows_person_0_0.csv",
+            "Person.id|Person.id|creationDate\n\
+             10|11|2010-01-01T00:00:00.000+0000\n\
+             11|12|2010-01-01T00:00:00.000+0000\n\
+             10|12|2010-01-01T00:00:00.000+0000\n",
+        );
+
+        let statistics = count(&dir, Context::builder().paths(true)).await.unwrap();
+        let paths = |pattern: &str| statistics.path2_cardinality.get(pattern).copied();
+        assert_eq!(
+            paths("(Person)-[KNOWS]->(Person)-[KNOWS]->(Person)"),
+            Some(1.0)
+        );
+        // pairs of distinct edges leaving 10 and entering 12, in either order
+        assert_eq!(
+            paths("(Person)<-[KNOWS]-(Person)-[KNOWS]->(Person)"),
+            Some(2.0)
+        );
+        assert_eq!(
+            paths("(Person)-[KNOWS]->(Person)<-[KNOWS]-(Person)"),
+            Some(2.0)
+        );
+        // 10 and 11 live in Delhi, 12 in Mumbai
+        let located = "(Person)-[IS_LOCATED_IN]->(City)<-[IS_LOCATED_IN]-(Person)";
+        assert_eq!(paths(located), Some(2.0));
+        let located = "(Person)-[IS_LOCATED_IN]->(Place)<-[IS_LOCATED_IN]-(Person)";
+        assert_eq!(paths(located), Some(2.0));
+        for end in ["City", "Place"] {
+            let pattern = format!("(Person)-[KNOWS]->(Person)-[IS_LOCATED_IN]->({})", end);
+            assert_eq!(paths(&pattern), Some(3.0));
+        }
+        // no vertex has two outgoing IS_LOCATED_IN edges
+        let pattern = "(City)<-[IS_LOCATED_IN]-(Person)-[IS_LOCATED_IN]->(City)";
+        assert_eq!(paths(pattern), None);
+    }
+
+    fn exact(out_degrees: &[(u64, u32)], in_degrees: &[(u64, u32)]) -> Endpoints {
+        Endpoints::Exact {
+            out_degrees: out_degrees.iter().copied().collect(),
+            in_degrees: in_degrees.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn rolls_up_degrees_per_concrete_label() {
+        // (City)-[IS_PART_OF]->(Country) and (Country)-[IS_PART_OF]->(Continent)
+        // rolled up to (Place)-[IS_PART_OF]->(Place), plus a second triple of
+        // City sources
+        let city_country = exact(&[(1, 1), (2, 1)], &[(1, 2)]);
+        let country_continent = exact(&[(1, 1)], &[(1, 1)]);
+        let city_city = exact(&[(1, 2)], &[(2, 2)]);
+        let rollup: Rollup = vec![
+            ("City", "Country", &city_country),
+            ("Country", "Continent", &country_continent),
+            ("City", "City", &city_city),
+        ];
+
+        let mut out_degrees = rolled_up_degrees(&rollup, Direction::Outgoing);
+        out_degrees.sort_unstable();
+        // City 1 has edges in two triples, Country 1 shares its id but not its label
+        assert_eq!(out_degrees, [1, 1, 3]);
+        let mut in_degrees = rolled_up_degrees(&rollup, Direction::Incoming);
+        in_degrees.sort_unstable();
+        assert_eq!(in_degrees, [1, 2, 2]);
+    }
+
+    #[test]
+    fn buckets_times_by_granularity() {
+        // 2000-02-29T12:00:00Z
+        let millis = 951_825_600_000;
+        assert_eq!(Granularity::Day.bucket(millis), "2000-02-29");
+        assert_eq!(Granularity::Month.bucket(millis), "2000-02");
+        assert_eq!(Granularity::Year.bucket(millis), "2000");
+        assert_eq!(Granularity::Day.bucket(0), "1970-01-01");
+        // times before 1970 fall in the day they are part of
+        assert_eq!(Granularity::Day.bucket(-1), "1969-12-31");
+        assert_eq!(Granularity::Day.bucket(-86_400_000), "1969-12-31");
+        assert_eq!(Granularity::Day.bucket(-86_400_001), "1969-12-30");
+        assert_eq!(Granularity::Year.bucket(-2_208_988_800_001), "1899");
+    }
+
+    #[test]
+    fn buckets_rows_by_creation_date() {
+        let counts = |time_series| {
+            Counts::new(Arc::new(Options {
+                time_series,
+                ..Options::default()
+            }))
+        };
+        let property = |index, name: &str| Property {
+            index,
+            name: name.to_owned(),
+            property_type: None,
+        };
+        let properties = [property(0, "id"), property(1, CREATION_DATE)];
+        let header = csv::StringRecord::from(vec!["id", CREATION_DATE]);
+        let row = |fields: &[&str]| Row {
+            path: Path::new("person_0_0.csv"),
+            header: &header,
+            position: Position::Row(1),
+            record: csv::StringRecord::from(fields.to_vec()),
+        };
+
+        let months = counts(Some(Granularity::Month));
+        let bucket = |fields: &[&str]| months.bucket(&row(fields), &properties);
+        assert_eq!(
+            bucket(&["1", "2010-05-01T00:00:00.000+0000"]).as_deref(),
+            Some("2010-05")
+        );
+        assert_eq!(bucket(&["1", "1272672000000"]).as_deref(), Some("2010-05"));
+        assert_eq!(
+            bucket(&["1", "1969-12-31T23:00:00.000+0000"]).as_deref(),
+            Some("1969-12")
+        );
+        for fields in [
+            &["1", ""][..],
+            &["1", "yesterday"],
+            &["1", "2010-13-01"],
+            &["1"],
+        ] {
+            assert_eq!(bucket(fields), None);
+        }
+        let row = row(&["1", "2010-05-01"]);
+        assert_eq!(months.bucket(&row, &properties[..1]), None);
+        assert_eq!(counts(None).bucket(&row, &properties), None);
+    }
+
+    fn characteristic_set(count: u64, multiplicities: &[(&str, f64)]) -> CharacteristicSet {
+        CharacteristicSet {
+            count,
+            multiplicities: multiplicities
+                .iter()
+                .map(|&(label, multiplicity)| (label.to_owned(), multiplicity))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn groups_vertices_by_the_labels_of_their_edges() {
+        let edge_counts = |out_degrees: &[(u64, u32)]| EdgeCounts {
+            count: out_degrees.iter().map(|&(_, degree)| degree as u64).sum(),
+            endpoints: exact(out_degrees, &[]),
+            sampled: None,
+            pairs: None,
+        };
+        let mut edges: HashMap<String, HashMap<String, EdgeCounts>> = HashMap::new();
+        for (edge_label, dst_label, out_degrees) in [
+            ("KNOWS", "Person", &[(1, 2), (2, 1), (3, 1)][..]),
+            (
+                "IS_LOCATED_IN",
+                "City",
+                &[(1, 1), (2, 1), (3, 1), (4, 1), (5, 1)],
+            ),
+            ("WORK_AT", "Company", &[(1, 1), (2, 1)]),
+            ("WORK_AT", "University", &[(1, 2)]),
+        ] {
+            edges
+                .entry(edge_label.to_owned())
+                .or_default()
+                .insert(dst_label.to_owned(), edge_counts(out_degrees));
+        }
+
+        // ordered by count, then by labels
+        let mut sets = Vec::new();
+        merge_characteristic_sets(&mut sets, &characteristic_sets(&edges));
+        let expected = [
+            characteristic_set(2, &[("IS_LOCATED_IN", 1.0)]),
+            // person 1 works at a company and two universities
+            characteristic_set(
+                2,
+                &[("IS_LOCATED_IN", 1.0), ("KNOWS", 1.5), ("WORK_AT", 2.0)],
+            ),
+            characteristic_set(1, &[("IS_LOCATED_IN", 1.0), ("KNOWS", 1.0)]),
+        ];
+        assert_eq!(sets, expected);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::statistics::CycleStatistics;
+
+/// Counts the triangles and 4-cycles of the graph of `edges`, taken as
+/// undirected and without parallel edges and self-loops.
+///
+/// Triangles are listed along edges oriented from the lower to the higher
+/// ranked endpoint, ranked by degree, and 4-cycles are counted once from their
+/// highest ranked vertex (Chiba and Nishizeki, "Arboricity and Subgraph
+/// Listing Algorithms"), so that hubs are not expanded.
+pub fn cycle_statistics(edges: &[(u64, u64)]) -> CycleStatistics {
+    let mut index = HashMap::new();
+    let mut pairs = Vec::with_capacity(edges.len());
+    for &(src, dst) in edges {
+        if src == dst {
+            continue;
+        }
+        let next = index.len() as u32;
+        let src = *index.entry(src).or_insert(next);
+        let next = index.len() as u32;
+        let dst = *index.entry(dst).or_insert(next);
+        pairs.push((src.min(dst), src.max(dst)));
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+
+    let n = index.len();
+    let mut adjacency = vec![Vec::new(); n];
+    for &(a, b) in &pairs {
+        adjacency[a as usize].push(b);
+        adjacency[b as usize].push(a);
+    }
+    let mut order = (0..n as u32).collect::<Vec<_>>();
+    order.sort_unstable_by_key(|&v| (adjacency[v as usize].len(), v));
+    let mut rank = vec![0; n];
+    for (i, &v) in order.iter().enumerate() {
+        rank[v as usize] = i;
+    }
+    // neighbours of each vertex ranked above it
+    let higher = adjacency
+        .iter()
+        .enumerate()
+        .map(|(v, neighbours)| {
+            neighbours
+                .iter()
+                .copied()
+                .filter(|&w| rank[w as usize] > rank[v])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut triangles = 0u64;
+    let mut vertex_triangles = vec![0u64; n];
+    let mut marked = vec![u32::MAX; n];
+    for u in 0..n as u32 {
+        for &v in &higher[u as usize] {
+            marked[v as usize] = u;
+        }
+        for &v in &higher[u as usize] {
+            for &w in &higher[v as usize] {
+                if marked[w as usize] == u {
+                    triangles += 1;
+                    for x in [u, v, w] {
+                        vertex_triangles[x as usize] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut four_cycles = 0u64;
+    let mut paths = vec![0u64; n];
+    let mut touched = Vec::new();
+    for v in 0..n {
+        for &u in &adjacency[v] {
+            if rank[u as usize] > rank[v] {
+                continue;
+            }
+            for &w in &adjacency[u as usize] {
+                if rank[w as usize] < rank[v] {
+                    if paths[w as usize] == 0 {
+                        touched.push(w);
+                    }
+                    paths[w as usize] += 1;
+                }
+            }
+        }
+        // any two paths v-u-w close a cycle
+        for w in touched.drain(..) {
+            let count = std::mem::take(&mut paths[w as usize]);
+            four_cycles += count * (count - 1) / 2;
+        }
+    }
+
+    let wedges = adjacency
+        .iter()
+        .map(|neighbours| {
+            let degree = neighbours.len() as u64;
+            degree * degree.saturating_sub(1) / 2
+        })
+        .sum::<u64>();
+    let local_clustering = adjacency
+        .iter()
+        .zip(&vertex_triangles)
+        .map(|(neighbours, &triangles)| match neighbours.len() as u64 {
+            degree @ 2.. => 2.0 * triangles as f64 / (degree * (degree - 1)) as f64,
+            _ => 0.0,
+        })
+        .sum::<f64>();
+
+    CycleStatistics {
+        vertices: n as u64,
+        edges: pairs.len() as u64,
+        wedges,
+        triangles,
+        four_cycles,
+        clustering_coefficient: match wedges {
+            0 => 0.0,
+            wedges => 3.0 * triangles as f64 / wedges as f64,
+        },
+        average_clustering: match n {
+            0 => 0.0,
+            n => local_clustering / n as f64,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_a_triangle() {
+        // parallel and reversed edges and self-loops do not count
+        let statistics = cycle_statistics(&[(1, 2), (2, 3), (3, 1), (2, 1), (1, 2), (3, 3)]);
+        assert_eq!(statistics.vertices, 3);
+        assert_eq!(statistics.edges, 3);
+        assert_eq!(statistics.wedges, 3);
+        assert_eq!(statistics.triangles, 1);
+        assert_eq!(statistics.four_cycles, 0);
+        assert_eq!(statistics.clustering_coefficient, 1.0);
+        assert_eq!(statistics.average_clustering, 1.0);
+    }
+
+    #[test]
+    fn counts_a_four_cycle() {
+        let statistics = cycle_statistics(&[(1, 2), (2, 3), (3, 4), (4, 1)]);
+        assert_eq!(statistics.vertices, 4);
+        assert_eq!(statistics.edges, 4);
+        assert_eq!(statistics.wedges, 4);
+        assert_eq!(statistics.triangles, 0);
+        assert_eq!(statistics.four_cycles, 1);
+        assert_eq!(statistics.clustering_coefficient, 0.0);
+        assert_eq!(statistics.average_clustering, 0.0);
+    }
+
+    #[test]
+    fn counts_a_complete_graph_of_four_vertices() {
+        let statistics = cycle_statistics(&[(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)]);
+        assert_eq!(statistics.vertices, 4);
+        assert_eq!(statistics.edges, 6);
+        assert_eq!(statistics.wedges, 12);
+        assert_eq!(statistics.triangles, 4);
+        assert_eq!(statistics.four_cycles, 3);
+        assert_eq!(statistics.clustering_coefficient, 1.0);
+        assert_eq!(statistics.average_clustering, 1.0);
+    }
+}
@@ -0,0 +1,284 @@
+use std::{fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Location of a record inside an input file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// Line and byte offset in a CSV file.
+    Line { line: u64, byte: u64 },
+    /// Row number in a Parquet file, starting at 1.
+    Row(u64),
+}
+
+impl From<&csv::Position> for Position {
+    fn from(pos: &csv::Position) -> Self {
+        Position::Line {
+            line: pos.line(),
+            byte: pos.byte(),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Position::Line { line, byte } => write!(f, "line {}, byte {}", line, byte),
+            Position::Row(row) => write!(f, "row {}", row),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Csv {
+        path: PathBuf,
+        source: csv::Error,
+    },
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    Parquet {
+        path: PathBuf,
+        source: parquet::errors::ParquetError,
+    },
+    /// The schema file could not be parsed.
+    Schema {
+        path: PathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The file name does not map to a known vertex or edge label.
+    FileName {
+        path: PathBuf,
+    },
+    /// A header column could not be interpreted.
+    Header {
+        path: PathBuf,
+        column: usize,
+        name: String,
+        reason: &'static str,
+    },
+    /// The header lacks a column required for the file's role.
+    MissingColumn {
+        path: PathBuf,
+        role: &'static str,
+    },
+    /// A record has fewer fields than the header requires.
+    ShortRow {
+        path: PathBuf,
+        position: Position,
+        column: String,
+        len: usize,
+    },
+    InvalidId {
+        path: PathBuf,
+        position: Position,
+        column: String,
+        value: String,
+    },
+    DuplicateId {
+        path: PathBuf,
+        position: Position,
+        column: String,
+        id: u64,
+        label: String,
+    },
+    /// An edge endpoint does not refer to any vertex with the given label.
+    UnknownEndpoint {
+        path: PathBuf,
+        position: Position,
+        column: String,
+        id: u64,
+        label: String,
+    },
+    /// A foreign-key column of a vertex row does not refer to any vertex with
+    /// the given label. Only the edge is left out, the vertex itself is valid.
+    UnknownForeignKey {
+        path: PathBuf,
+        position: Position,
+        column: String,
+        id: u64,
+        label: String,
+    },
+    /// A pattern passed to the estimator could not be parsed.
+    Pattern {
+        pattern: String,
+        offset: usize,
+        reason: &'static str,
+    },
+    /// A pattern passed to the estimator names a label the statistics do not
+    /// know.
+    UnknownLabel {
+        pattern: String,
+        /// `vertex` or `edge`.
+        kind: &'static str,
+        label: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Csv { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Json { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parquet { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Schema { path, source } => {
+                write!(f, "{}: invalid schema: {}", path.display(), source)
+            }
+            Error::FileName { path } => write!(
+                f,
+                "{}: file name does not match any vertex or edge label",
+                path.display()
+            ),
+            Error::Header {
+                path,
+                column,
+                name,
+                reason,
+            } => write!(
+                f,
+                "{}: header column {} {:?}: {}",
+                path.display(),
+                column,
+                name,
+                reason
+            ),
+            Error::MissingColumn { path, role } => {
+                write!(f, "{}: header has no {} column", path.display(), role)
+            }
+            Error::ShortRow {
+                path,
+                position,
+                column,
+                len,
+            } => write!(
+                f,
+                "{}: {}: missing column {:?} (record has {} fields)",
+                path.display(),
+                position,
+                column,
+                len
+            ),
+            Error::InvalidId {
+                path,
+                position,
+                column,
+                value,
+            } => write!(
+                f,
+                "{}: {}, column {:?}: invalid id {:?}",
+                path.display(),
+                position,
+                column,
+                value
+            ),
+            Error::DuplicateId {
+                path,
+                position,
+                column,
+                id,
+                label,
+            } => write!(
+                f,
+                "{}: {}, column {:?}: duplicate {} id {}",
+                path.display(),
+                position,
+                column,
+                label,
+                id
+            ),
+            Error::UnknownEndpoint {
+                path,
+                position,
+                column,
+                id,
+                label,
+            } => write!(
+                f,
+                "{}: {}, column {:?}: no {} vertex with id {}",
+                path.display(),
+                position,
+                column,
+                label,
+                id
+            ),
+            Error::UnknownForeignKey {
+                path,
+                position,
+                column,
+                id,
+                label,
+            } => write!(
+                f,
+                "{}: {}, column {:?}: foreign key to no {} vertex with id {}",
+                path.display(),
+                position,
+                column,
+                label,
+                id
+            ),
+            Error::Pattern {
+                pattern,
+                offset,
+                reason,
+            } => write!(f, "pattern {:?}, offset {}: {}", pattern, offset, reason),
+            Error::UnknownLabel {
+                pattern,
+                kind,
+                label,
+            } => write!(
+                f,
+                "pattern {:?}: unknown {} label {:?}",
+                pattern, kind, label
+            ),
+        }
+    }
+}
+
+/// Why a single record was rejected, used to tally rows skipped in lenient mode.
+/// `UnknownForeignKey` tallies foreign-key edges left out of rows whose vertex
+/// is counted all the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    ShortRow,
+    InvalidId,
+    DuplicateId,
+    UnknownEndpoint,
+    UnknownForeignKey,
+}
+
+impl Error {
+    /// Returns the reason if this error concerns a single record only.
+    pub fn skip_reason(&self) -> Option<SkipReason> {
+        match self {
+            Error::ShortRow { .. } => Some(SkipReason::ShortRow),
+            Error::InvalidId { .. } => Some(SkipReason::InvalidId),
+            Error::DuplicateId { .. } => Some(SkipReason::DuplicateId),
+            Error::UnknownEndpoint { .. } => Some(SkipReason::UnknownEndpoint),
+            Error::UnknownForeignKey { .. } => Some(SkipReason::UnknownForeignKey),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Csv { source, .. } => Some(source),
+            Error::Json { source, .. } => Some(source),
+            Error::Parquet { source, .. } => Some(source),
+            Error::Schema { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
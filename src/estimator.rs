@@ -0,0 +1,380 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    error::{Error, Result},
+    statistics::Statistics,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// `-[LABEL]->`
+    Outgoing,
+    /// `<-[LABEL]-`
+    Incoming,
+}
+
+impl Direction {
+    pub fn reverse(self) -> Self {
+        match self {
+            Direction::Outgoing => Direction::Incoming,
+            Direction::Incoming => Direction::Outgoing,
+        }
+    }
+}
+
+/// An edge of a path pattern and the vertex it leads to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub label: String,
+    pub direction: Direction,
+    pub vertex: String,
+}
+
+/// A path pattern such as `(Person)-[KNOWS]->(Person)-[IS_LOCATED_IN]->(City)`.
+///
+/// Empty labels, as in `()` and `-[]->`, match any label. A bare name, as in
+/// `(p)`, is always a label; a variable needs a colon, e.g. `(p:Person)` or
+/// `(p:)`, and is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub start: String,
+    pub steps: Vec<Step>,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({})", self.start)?;
+        for step in &self.steps {
+            match step.direction {
+                Direction::Outgoing => write!(f, "-[{}]->({})", step.label, step.vertex)?,
+                Direction::Incoming => write!(f, "<-[{}]-({})", step.label, step.vertex)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Parser<'a> {
+    pattern: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &'static str) -> Error {
+        Error::Pattern {
+            pattern: self.pattern.to_owned(),
+            offset: self.pattern.len() - self.rest.len(),
+            reason,
+        }
+    }
+
+    /// Consumes `token` if the input continues with it, after any whitespace.
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str, reason: &'static str) -> Result<()> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error(reason)),
+        }
+    }
+
+    /// Reads the label up to the closing `close`, dropping a `variable:` prefix.
+    fn label(&mut self, close: char) -> Result<String> {
+        let end = self
+            .rest
+            .find(close)
+            .ok_or_else(|| self.error("unclosed bracket"))?;
+        let inner = &self.rest[..end];
+        let label = inner
+            .split_once(':')
+            .map_or(inner, |(_, label)| label)
+            .trim();
+        if !label.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(self.error("invalid label"));
+        }
+        self.rest = &self.rest[end + close.len_utf8()..];
+        Ok(label.to_owned())
+    }
+
+    fn vertex(&mut self) -> Result<String> {
+        self.expect("(", "expected `(`")?;
+        self.label(')')
+    }
+
+    fn step(&mut self) -> Result<Step> {
+        let incoming = self.eat("<-");
+        if !incoming {
+            self.expect("-", "expected `-` or `<-`")?;
+        }
+        self.expect("[", "expected `[`")?;
+        let label = self.label(']')?;
+        let direction = match incoming {
+            true => {
+                self.expect("-", "expected `-`")?;
+                Direction::Incoming
+            }
+            false => {
+                self.expect("->", "expected `->`")?;
+                Direction::Outgoing
+            }
+        };
+        let vertex = self.vertex()?;
+        Ok(Step {
+            label,
+            direction,
+            vertex,
+        })
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            pattern: s,
+            rest: s,
+        };
+        let start = parser.vertex()?;
+        let mut steps = Vec::new();
+        while !parser.rest.trim().is_empty() {
+            steps.push(parser.step()?);
+        }
+        Ok(Pattern { start, steps })
+    }
+}
+
+/// Estimates result cardinalities of path patterns from `Statistics`.
+///
+/// Every step multiplies the estimate by the average number of matching edges
+/// per vertex of its source, assuming edges of consecutive steps are
+/// independent, so `(a)-[x]->(b)-[y]->(c)` is estimated as
+/// `|a x b| * |b y c| / |b|`. Where `path2_cardinality` counts the paths of a
+/// step and the one before, it multiplies by their number per edge of the
+/// previous step instead.
+#[derive(Debug, Clone, Copy)]
+pub struct Estimator<'a> {
+    statistics: &'a Statistics,
+}
+
+impl<'a> Estimator<'a> {
+    pub fn new(statistics: &'a Statistics) -> Self {
+        Estimator { statistics }
+    }
+
+    pub fn vertices(&self, label: &str) -> f64 {
+        self.statistics
+            .vertex_cardinality
+            .get(label)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn edges(&self, src_label: &str, edge_label: &str, dst_label: &str) -> f64 {
+        self.statistics
+            .edge_cardinality
+            .get(src_label)
+            .and_then(|edges| edges.get(edge_label))
+            .and_then(|dsts| dsts.get(dst_label))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Number of edges matching `step` from a vertex labelled `from`.
+    fn step_edges(&self, from: &str, step: &Step) -> f64 {
+        match step.direction {
+            Direction::Outgoing => self.edges(from, &step.label, &step.vertex),
+            Direction::Incoming => self.edges(&step.vertex, &step.label, from),
+        }
+    }
+
+    /// Number of paths of the two steps from `start`, if counted.
+    pub fn paths2(&self, start: &str, first: &Step, second: &Step) -> Option<f64> {
+        let pattern = Pattern {
+            start: start.to_owned(),
+            steps: vec![first.clone(), second.clone()],
+        };
+        self.statistics
+            .path2_cardinality
+            .get(&pattern.to_string())
+            .copied()
+    }
+
+    /// Fails on labels the statistics do not know, such as a variable taken for
+    /// a label in `(p)`, rather than estimating 0.
+    pub fn estimate(&self, pattern: &Pattern) -> Result<f64> {
+        self.check(pattern)?;
+        Ok(self.estimate_known(pattern))
+    }
+
+    fn check(&self, pattern: &Pattern) -> Result<()> {
+        let unknown = |kind, label: &str| Error::UnknownLabel {
+            pattern: pattern.to_string(),
+            kind,
+            label: label.to_owned(),
+        };
+        let vertex_labels =
+            std::iter::once(&pattern.start).chain(pattern.steps.iter().map(|step| &step.vertex));
+        for label in vertex_labels {
+            if !label.is_empty() && !self.statistics.vertex_cardinality.contains_key(label) {
+                return Err(unknown("vertex", label));
+            }
+        }
+        for step in &pattern.steps {
+            let known = step.label.is_empty()
+                || self
+                    .statistics
+                    .edge_cardinality
+                    .values()
+                    .any(|edges| edges.contains_key(&step.label));
+            if !known {
+                return Err(unknown("edge", &step.label));
+            }
+        }
+        Ok(())
+    }
+
+    fn estimate_known(&self, pattern: &Pattern) -> f64 {
+        let mut estimate = self.vertices(&pattern.start);
+        let mut previous = &pattern.start;
+        // the step before and the label it started from
+        let mut last: Option<(&str, &Step)> = None;
+        for step in &pattern.steps {
+            let vertices = self.vertices(previous);
+            if vertices == 0.0 {
+                return 0.0;
+            }
+            let paths = last.and_then(|(from, last)| {
+                Some((self.paths2(from, last, step)?, self.step_edges(from, last)))
+            });
+            estimate *= match paths {
+                Some((_, 0.0)) => return 0.0,
+                Some((paths, edges)) => paths / edges,
+                None => self.step_edges(previous, step) / vertices,
+            };
+            last = Some((previous, step));
+            previous = &step.vertex;
+        }
+        estimate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(label: &str, direction: Direction, vertex: &str) -> Step {
+        Step {
+            label: label.to_owned(),
+            direction,
+            vertex: vertex.to_owned(),
+        }
+    }
+
+    fn statistics() -> Statistics {
+        let mut statistics = Statistics::default();
+        for (label, count) in [("Person", 10.0), ("City", 5.0), ("", 15.0)] {
+            statistics
+                .vertex_cardinality
+                .insert(label.to_owned(), count);
+        }
+        for (src_label, edge_label, dst_label, count) in [
+            ("Person", "KNOWS", "Person", 40.0),
+            ("Person", "IS_LOCATED_IN", "City", 10.0),
+        ] {
+            statistics
+                .edge_cardinality
+                .entry(src_label.to_owned())
+                .or_default()
+                .entry(edge_label.to_owned())
+                .or_default()
+                .insert(dst_label.to_owned(), count);
+        }
+        statistics
+    }
+
+    #[test]
+    fn parses_patterns() {
+        let pattern = "(p:Person) <-[ ]- ()-[:IS_LOCATED_IN]->(City)"
+            .parse::<Pattern>()
+            .unwrap();
+        assert_eq!(
+            pattern,
+            Pattern {
+                start: "Person".to_owned(),
+                steps: vec![
+                    step("", Direction::Incoming, ""),
+                    step("IS_LOCATED_IN", Direction::Outgoing, "City"),
+                ],
+            }
+        );
+        assert_eq!(
+            pattern.to_string(),
+            "(Person)<-[]-()-[IS_LOCATED_IN]->(City)"
+        );
+        // a bare name is a label rather than a variable
+        assert_eq!("(p)".parse::<Pattern>().unwrap().start, "p");
+    }
+
+    #[test]
+    fn reports_the_offset_of_parse_errors() {
+        let offset = |pattern: &str| match pattern.parse::<Pattern>() {
+            Err(Error::Pattern { offset, .. }) => offset,
+            parsed => panic!("{:?}", parsed),
+        };
+        assert_eq!(offset("Person"), 0);
+        assert_eq!(offset("(Person)-(KNOWS)"), 9);
+        assert_eq!(offset("(Person)-[KNOWS]-(Person)"), 16);
+        assert_eq!(offset("(Person)-[KNOWS->(Person)"), 10);
+        assert_eq!(offset("(Person)-[KNOWS]->(Per-son)"), 19);
+    }
+
+    #[test]
+    fn estimates_paths_from_edges_per_vertex() {
+        let statistics = statistics();
+        let estimator = Estimator::new(&statistics);
+        let estimate = |pattern: &str| estimator.estimate(&pattern.parse().unwrap()).unwrap();
+        assert_eq!(estimate("(Person)"), 10.0);
+        assert_eq!(estimate("(Person)-[KNOWS]->(Person)"), 40.0);
+        assert_eq!(estimate("(City)<-[IS_LOCATED_IN]-(Person)"), 10.0);
+        // 10 persons, each knowing 4 persons located in 1 city
+        let path = "(Person)-[KNOWS]->(Person)-[IS_LOCATED_IN]->(City)";
+        assert_eq!(estimate(path), 40.0);
+        assert_eq!(estimate("(City)-[KNOWS]->(Person)"), 0.0);
+    }
+
+    #[test]
+    fn estimates_paths_from_counted_paths() {
+        let path = "(Person)-[KNOWS]->(Person)-[IS_LOCATED_IN]->(City)";
+        let mut statistics = statistics();
+        statistics.path2_cardinality.insert(path.to_owned(), 30.0);
+        let estimator = Estimator::new(&statistics);
+        let estimate = |pattern: &str| estimator.estimate(&pattern.parse().unwrap()).unwrap();
+        assert_eq!(estimate(path), 30.0);
+        // the counted paths, each continued by the 2 persons per city
+        let longer = "(Person)-[KNOWS]->(Person)-[IS_LOCATED_IN]->(City)<-[IS_LOCATED_IN]-(Person)";
+        assert_eq!(estimate(longer), 30.0 * 10.0 / 5.0);
+    }
+
+    #[test]
+    fn rejects_unknown_labels() {
+        let statistics = statistics();
+        let estimator = Estimator::new(&statistics);
+        let label = |pattern: &str| match estimator.estimate(&pattern.parse().unwrap()) {
+            Err(Error::UnknownLabel { kind, label, .. }) => (kind, label),
+            estimate => panic!("{:?}", estimate),
+        };
+        assert_eq!(label("(Persn)"), ("vertex", "Persn".to_owned()));
+        assert_eq!(label("(p)-[KNOWS]->(q)"), ("vertex", "p".to_owned()));
+        assert_eq!(label("(Person)-[KNOW]->()"), ("edge", "KNOW".to_owned()));
+    }
+}
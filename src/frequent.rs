@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+/// Number of counters of a `Frequent` summary. Values occurring in more than
+/// 1/257th of the rows are always kept.
+pub const CAPACITY: usize = 256;
+
+/// The most frequent values of a stream in bounded space, using the
+/// Misra-Gries algorithm. Columns with at most `CAPACITY` distinct values are
+/// counted exactly.
+#[derive(Debug, Clone, Default)]
+pub struct Frequent {
+    counters: HashMap<String, u64>,
+    /// How much any count may be too low, which is also the most often a value
+    /// without a counter may occur.
+    error: u64,
+}
+
+impl Frequent {
+    pub fn insert(&mut self, value: &str) {
+        if let Some(count) = self.counters.get_mut(value) {
+            *count += 1;
+        } else if self.counters.len() < CAPACITY {
+            self.counters.insert(value.to_owned(), 1);
+        } else {
+            // the new value and one occurrence of every counted value cancel out
+            self.counters.retain(|_, count| {
+                *count -= 1;
+                *count > 0
+            });
+            self.error += 1;
+        }
+    }
+
+    /// Adds the counters of a summary of another part of the stream, keeping
+    /// the error bound of a single summary (Agarwal et al., "Mergeable
+    /// Summaries").
+    pub fn merge(&mut self, other: &Frequent) {
+        for (value, &count) in &other.counters {
+            *self.counters.entry(value.clone()).or_default() += count;
+        }
+        self.error += other.error;
+        if self.counters.len() > CAPACITY {
+            let mut counts = self.counters.values().copied().collect::<Vec<_>>();
+            counts.sort_unstable_by(|a, b| b.cmp(a));
+            let excess = counts[CAPACITY];
+            self.counters.retain(|_, count| {
+                *count = count.saturating_sub(excess);
+                *count > 0
+            });
+            self.error += excess;
+        }
+    }
+
+    /// Returns up to `k` of the most frequent values with their counts, most
+    /// frequent first. Values whose count is within the error are left out, as
+    /// values without a counter may occur as often.
+    pub fn top(self, k: usize) -> Vec<(String, u64)> {
+        let error = self.error;
+        let mut values = self
+            .counters
+            .into_iter()
+            .filter(|&(_, count)| count > error)
+            .collect::<Vec<_>>();
+        values.sort_unstable_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        values.truncate(k);
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stream of `n` rows where value `i` occurs `weight(i)` times.
+    fn stream(values: std::ops::Range<u64>, weight: impl Fn(u64) -> u64) -> Vec<String> {
+        values
+            .flat_map(|i| std::iter::repeat_n(i.to_string(), weight(i) as usize))
+            .collect()
+    }
+
+    fn summarise(rows: &[String]) -> Frequent {
+        let mut frequent = Frequent::default();
+        for row in rows {
+            frequent.insert(row);
+        }
+        frequent
+    }
+
+    #[test]
+    fn counts_few_distinct_values_exactly() {
+        let frequent = summarise(&stream(0..CAPACITY as u64, |i| i % 3 + 1));
+        assert_eq!(frequent.error, 0);
+        let top = frequent.top(3);
+        // ties are ordered by value
+        let expected = [("101", 3), ("104", 3), ("107", 3)];
+        let expected = expected.map(|(value, count)| (value.to_owned(), count));
+        assert_eq!(top, expected);
+    }
+
+    #[test]
+    fn keeps_frequent_values_of_many_distinct_ones() {
+        // two heavy hitters among 10,000 values occurring once
+        let mut rows = stream(0..10_000, |_| 1);
+        rows.extend(stream(0..2, |i| 500 - 100 * i));
+        let frequent = summarise(&rows);
+        assert!(frequent.error <= rows.len() as u64 / (CAPACITY as u64 + 1));
+        let top = frequent.top(10);
+        let values = top
+            .iter()
+            .map(|(value, _)| value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(values, ["0", "1"]);
+    }
+
+    #[test]
+    fn merges_within_the_error_bound() {
+        let weight = |i: u64| match i % 100 {
+            0 => 50,
+            _ => 1,
+        };
+        let left = stream(0..6_000, weight);
+        let right = stream(3_000..9_000, weight);
+        let mut frequent = summarise(&left);
+        frequent.merge(&summarise(&right));
+
+        let rows = (left.len() + right.len()) as u64;
+        assert!(frequent.counters.len() <= CAPACITY);
+        assert!(
+            frequent.error <= rows / (CAPACITY as u64 + 1),
+            "{}",
+            frequent.error
+        );
+        let mut counts = HashMap::<&str, u64>::new();
+        for row in left.iter().chain(&right) {
+            *counts.entry(row).or_default() += 1;
+        }
+        for (value, &count) in &counts {
+            let estimate = frequent.counters.get(*value).copied().unwrap_or(0);
+            assert!(estimate <= count);
+            assert!(estimate + frequent.error >= count, "{} {}", value, count);
+        }
+        // the 30 values in both halves occur 100 times, those in one half 50
+        // times, which is within the error
+        let top = frequent.top(40);
+        assert_eq!(top.len(), 30);
+        assert!(top.iter().all(|(value, _)| counts[value.as_str()] == 100));
+    }
+}
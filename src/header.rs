@@ -0,0 +1,357 @@
+use std::path::Path;
+
+use crate::{
+    error::{Error, Result},
+    property::{Property, PropertyType},
+    schema::{ForeignKey, VertexMapping},
+};
+
+/// Header conventions of the supported input formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// `neo4j-admin import` headers such as `id:ID(Place)|:LABEL|name:STRING`
+    /// and `:START_ID(Person)|:END_ID(Person)`.
+    Neo4j,
+    /// Plain column names as written by the LDBC datagen `CsvBasic`,
+    /// `CsvMergeForeign` and `CsvComposite` serializers, e.g. `id|name|type`
+    /// and `Person.id|Person.id|creationDate`.
+    Raw,
+}
+
+impl Dialect {
+    /// Neo4j headers are recognised by their `name:TYPE` columns.
+    pub fn detect(header: &csv::StringRecord) -> Self {
+        if header.iter().any(|s| s.contains(':')) {
+            Dialect::Neo4j
+        } else {
+            Dialect::Raw
+        }
+    }
+}
+
+/// Splits a header column into its name and its Neo4j type, if any.
+pub fn split_column(s: &str) -> (&str, Option<&str>) {
+    match s.split_once(':') {
+        Some((name, prop_type)) => (name, Some(prop_type)),
+        None => (s, None),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VertexColumns {
+    pub dialect: Dialect,
+    pub id: usize,
+    pub label: Option<usize>,
+    pub foreign_keys: Vec<(usize, ForeignKey)>,
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EdgeColumns {
+    pub start: usize,
+    pub end: usize,
+    pub properties: Vec<Property>,
+}
+
+fn set_once(
+    path: &Path,
+    slot: &mut Option<usize>,
+    i: usize,
+    name: &str,
+    reason: &'static str,
+) -> Result<()> {
+    match slot.replace(i) {
+        Some(_) => Err(Error::Header {
+            path: path.to_owned(),
+            column: i,
+            name: name.to_owned(),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the property of column `index`, unless Neo4j is told to ignore it.
+fn property(
+    dialect: Dialect,
+    index: usize,
+    name: &str,
+    prop_type: Option<&str>,
+) -> Option<Property> {
+    let property_type = match (dialect, prop_type) {
+        (_, Some(t)) if t.eq_ignore_ascii_case("IGNORE") => return None,
+        (Dialect::Neo4j, Some(t)) => Some(PropertyType::from_neo4j(t)),
+        // untyped Neo4j columns hold strings
+        (Dialect::Neo4j, None) => Some(PropertyType::String),
+        (Dialect::Raw, _) => None,
+    };
+    Some(Property {
+        index,
+        name: name.to_owned(),
+        property_type,
+    })
+}
+
+fn missing(path: &Path, role: &'static str) -> Error {
+    Error::MissingColumn {
+        path: path.to_owned(),
+        role,
+    }
+}
+
+impl VertexColumns {
+    pub fn detect(
+        path: &Path,
+        header: &csv::StringRecord,
+        mapping: &VertexMapping,
+    ) -> Result<Self> {
+        let dialect = Dialect::detect(header);
+
+        let (mut id, mut label) = (None, None);
+        let mut foreign_keys = Vec::new();
+        let mut properties = Vec::new();
+
+        for (i, s) in header.iter().enumerate() {
+            let (name, prop_type) = split_column(s);
+            match (dialect, prop_type) {
+                (Dialect::Neo4j, Some(t)) if t.starts_with("ID") => {
+                    set_once(path, &mut id, i, s, "duplicate ID column")?
+                }
+                (Dialect::Neo4j, Some("LABEL")) => {
+                    set_once(path, &mut label, i, s, "duplicate LABEL column")?
+                }
+                (Dialect::Raw, _) if name.eq_ignore_ascii_case("id") => {
+                    set_once(path, &mut id, i, s, "duplicate id column")?
+                }
+                (Dialect::Raw, _)
+                    if mapping
+                        .label_column
+                        .as_ref()
+                        .is_some_and(|column| name.eq_ignore_ascii_case(column)) =>
+                {
+                    set_once(path, &mut label, i, s, "duplicate label column")?
+                }
+                _ => {
+                    if let Some(fk) = mapping
+                        .foreign_keys
+                        .iter()
+                        .find(|fk| name.eq_ignore_ascii_case(&fk.column))
+                    {
+                        foreign_keys.push((i, fk.clone()));
+                    } else {
+                        properties.extend(property(dialect, i, name, prop_type));
+                    }
+                }
+            }
+        }
+
+        // the label column is listed as a property too, to collect the most
+        // common concrete labels
+        if let Some(index) = label {
+            properties.push(Property {
+                index,
+                name: ":LABEL".to_owned(),
+                property_type: Some(PropertyType::String),
+            });
+        }
+
+        Ok(VertexColumns {
+            dialect,
+            id: id.ok_or_else(|| missing(path, "vertex id"))?,
+            label,
+            foreign_keys,
+            properties,
+        })
+    }
+}
+
+impl EdgeColumns {
+    /// Raw headers name their endpoint columns after the endpoint labels, so the
+    /// first two columns that look like ids (`Person.id`, `Person1Id`) are taken
+    /// as source and destination.
+    pub fn detect(path: &Path, header: &csv::StringRecord) -> Result<Self> {
+        let dialect = Dialect::detect(header);
+
+        let (mut start, mut end) = (None, None);
+        let mut properties = Vec::new();
+
+        for (i, s) in header.iter().enumerate() {
+            let (name, prop_type) = split_column(s);
+            match (dialect, prop_type) {
+                (Dialect::Neo4j, Some(t)) if t.starts_with("START_ID") => {
+                    set_once(path, &mut start, i, s, "duplicate START_ID column")?
+                }
+                (Dialect::Neo4j, Some(t)) if t.starts_with("END_ID") => {
+                    set_once(path, &mut end, i, s, "duplicate END_ID column")?
+                }
+                (Dialect::Raw, _)
+                    if (name.ends_with(".id") || name.ends_with("Id")) && end.is_none() =>
+                {
+                    if start.is_none() {
+                        start = Some(i);
+                    } else {
+                        end = Some(i);
+                    }
+                }
+                (Dialect::Neo4j, Some(t)) if t.eq_ignore_ascii_case("TYPE") => (),
+                _ => properties.extend(property(dialect, i, name, prop_type)),
+            }
+        }
+
+        Ok(EdgeColumns {
+            start: start.ok_or_else(|| missing(path, "edge source id"))?,
+            end: end.ok_or_else(|| missing(path, "edge destination id"))?,
+            properties,
+        })
+    }
+}
+
+/// Returns the concrete label stored in a label column. The raw datagen output
+/// writes them in lower case (`city`), which is normalised to `City`.
+pub fn normalise_label(dialect: Dialect, label: &str) -> String {
+    match dialect {
+        Dialect::Neo4j => label.to_owned(),
+        Dialect::Raw => {
+            let mut chars = label.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Schema;
+
+    fn header(s: &str) -> csv::StringRecord {
+        csv::StringRecord::from(s.split('|').collect::<Vec<_>>())
+    }
+
+    fn mapping(file: &str) -> VertexMapping {
+        Schema::default()
+            .vertices
+            .into_iter()
+            .find(|mapping| mapping.file == file)
+            .unwrap()
+    }
+
+    fn properties(properties: &[Property]) -> Vec<(usize, &str, Option<PropertyType>)> {
+        properties
+            .iter()
+            .map(|p| (p.index, p.name.as_str(), p.property_type))
+            .collect()
+    }
+
+    #[test]
+    fn detects_neo4j_vertex_headers() {
+        let header = header("id:ID(Place)|name:STRING|url:IGNORE|:LABEL|population:LONG");
+        let columns =
+            VertexColumns::detect(Path::new("place"), &header, &mapping("place")).unwrap();
+        assert_eq!(columns.dialect, Dialect::Neo4j);
+        assert_eq!((columns.id, columns.label), (0, Some(3)));
+        assert!(columns.foreign_keys.is_empty());
+        assert_eq!(
+            properties(&columns.properties),
+            [
+                (1, "name", Some(PropertyType::String)),
+                (4, "population", Some(PropertyType::Integer)),
+                (3, ":LABEL", Some(PropertyType::String)),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_neo4j_edge_headers() {
+        let header = header(":START_ID(Person)|:END_ID(Person)|creationDate:DATETIME|:TYPE");
+        let columns = EdgeColumns::detect(Path::new("knows"), &header).unwrap();
+        assert_eq!((columns.start, columns.end), (0, 1));
+        assert_eq!(
+            properties(&columns.properties),
+            [(2, "creationDate", Some(PropertyType::DateTime))]
+        );
+    }
+
+    #[test]
+    fn detects_csv_basic_edge_headers() {
+        let header = header("Person.id|Person.id|creationDate");
+        assert_eq!(Dialect::detect(&header), Dialect::Raw);
+        let columns = EdgeColumns::detect(Path::new("knows"), &header).unwrap();
+        assert_eq!((columns.start, columns.end), (0, 1));
+        assert_eq!(properties(&columns.properties), [(2, "creationDate", None)]);
+    }
+
+    #[test]
+    fn detects_spark_edge_headers() {
+        let header = header("creationDate|Person1Id|Person2Id");
+        let columns = EdgeColumns::detect(Path::new("knows"), &header).unwrap();
+        assert_eq!((columns.start, columns.end), (1, 2));
+        assert_eq!(properties(&columns.properties), [(0, "creationDate", None)]);
+    }
+
+    #[test]
+    fn detects_merge_foreign_vertex_headers() {
+        let header = header("id|creationDate|content|length|creator|Forum.id|place");
+        let columns = VertexColumns::detect(Path::new("post"), &header, &mapping("post")).unwrap();
+        assert_eq!(columns.dialect, Dialect::Raw);
+        assert_eq!((columns.id, columns.label), (0, None));
+        let foreign_keys = columns
+            .foreign_keys
+            .iter()
+            .map(|(index, fk)| (*index, fk.label.as_str(), fk.dst.as_str(), fk.reverse))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            foreign_keys,
+            [
+                (4, "HAS_CREATOR", "Person", false),
+                (5, "CONTAINER_OF", "Forum", true),
+                (6, "IS_LOCATED_IN", "Place", false),
+            ]
+        );
+        // the forum contains the post
+        let (_, forum) = &columns.foreign_keys[1];
+        assert_eq!(forum.endpoints("post", "forum"), ("forum", "post"));
+        assert_eq!(
+            properties(&columns.properties),
+            [
+                (1, "creationDate", None),
+                (2, "content", None),
+                (3, "length", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_id_columns() {
+        let (path, person) = (Path::new("person"), mapping("person"));
+        let neo4j = header("id:ID(Person)|other:ID(Person)|firstName:STRING");
+        match VertexColumns::detect(path, &neo4j, &person) {
+            Err(Error::Header { column, reason, .. }) => {
+                assert_eq!((column, reason), (1, "duplicate ID column"))
+            }
+            columns => panic!("{:?}", columns),
+        }
+        assert!(matches!(
+            VertexColumns::detect(path, &header("id|firstName|ID"), &person),
+            Err(Error::Header { column: 2, .. })
+        ));
+        let edges = header(":START_ID(Person)|:START_ID(Person)|:END_ID(Person)");
+        assert!(matches!(
+            EdgeColumns::detect(path, &edges),
+            Err(Error::Header { column: 1, .. })
+        ));
+        assert!(matches!(
+            VertexColumns::detect(path, &header("firstName"), &person),
+            Err(Error::MissingColumn { .. })
+        ));
+    }
+
+    #[test]
+    fn normalises_raw_labels() {
+        assert_eq!(normalise_label(Dialect::Raw, "city"), "City");
+        assert_eq!(normalise_label(Dialect::Raw, ""), "");
+        assert_eq!(normalise_label(Dialect::Neo4j, "city"), "city");
+    }
+}
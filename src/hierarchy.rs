@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+/// Concrete labels of the vertices of a parent label, e.g. `City`, `Country` and
+/// `Continent` for `Place`.
+#[derive(Debug, Clone, Default)]
+struct SubLabels {
+    labels: Vec<String>,
+    ids: HashMap<u64, u32>,
+}
+
+/// Registry of vertex ids whose concrete label is given by a `:LABEL` column,
+/// keyed by the label of the file they were read from.
+#[derive(Debug, Clone, Default)]
+pub struct LabelHierarchy {
+    sub_labels: HashMap<String, SubLabels>,
+    parents: HashMap<String, String>,
+}
+
+impl LabelHierarchy {
+    /// Records that vertex `id` of `parent` has the concrete label `label`.
+    ///
+    /// Returns `false` if `id` was already registered for `parent`.
+    pub fn insert(&mut self, parent: &str, id: u64, label: &str) -> bool {
+        let sub_labels = self.sub_labels.entry(parent.to_owned()).or_default();
+        if sub_labels.ids.contains_key(&id) {
+            return false;
+        }
+        let index = match sub_labels.labels.iter().position(|l| l == label) {
+            Some(index) => index,
+            None => {
+                sub_labels.labels.push(label.to_owned());
+                if label != parent {
+                    self.parents.insert(label.to_owned(), parent.to_owned());
+                }
+                sub_labels.labels.len() - 1
+            }
+        };
+        sub_labels.ids.insert(id, index as u32);
+        true
+    }
+
+    /// Resolves the concrete label of vertex `id` of `label`.
+    ///
+    /// Labels without registered sub-labels resolve to themselves, while ids
+    /// missing from a registered label resolve to `None`.
+    pub fn resolve<'a>(&'a self, label: &'a str, id: u64) -> Option<&'a str> {
+        match self.sub_labels.get(label) {
+            Some(sub_labels) => sub_labels
+                .ids
+                .get(&id)
+                .map(|&index| sub_labels.labels[index as usize].as_str()),
+            None => Some(label),
+        }
+    }
+
+    /// Returns `label` followed by its ancestors, closest first, e.g.
+    /// `[City, Place]`.
+    pub fn ancestry<'a>(&'a self, label: &'a str) -> Vec<&'a str> {
+        let mut labels = vec![label];
+        while let Some(parent) = self.parents.get(labels[labels.len() - 1]) {
+            if labels.contains(&parent.as_str()) {
+                break;
+            }
+            labels.push(parent);
+        }
+        labels
+    }
+
+    /// Records that `label` is a sub-label of `parent` without registering any
+    /// ids, e.g. to roll up the counts of an update like the initial import.
+    pub fn add_parent(&mut self, label: &str, parent: &str) {
+        self.parents.insert(label.to_owned(), parent.to_owned());
+    }
+
+    /// Returns the parent of each sub-label.
+    pub fn parents(&self) -> &HashMap<String, String> {
+        &self.parents
+    }
+
+    /// Adds the registrations of `other`, which must cover other parent labels.
+    pub fn extend(&mut self, other: LabelHierarchy) {
+        self.sub_labels.extend(other.sub_labels);
+        self.parents.extend(other.parents);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::sketch::mix;
+
+/// Number of values a `Reservoir` keeps.
+const RESERVOIR_SIZE: usize = 1 << 16;
+
+/// A uniform sample of a stream of values of bounded size (Vitter's
+/// algorithm R), from which histograms are built.
+#[derive(Debug, Clone, Default)]
+pub struct Reservoir {
+    /// Number of values offered so far.
+    seen: u64,
+    sample: Vec<f64>,
+    /// State of the random number generator, seeded deterministically so that
+    /// runs are reproducible.
+    state: u64,
+}
+
+impl Reservoir {
+    fn random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        mix(self.state)
+    }
+
+    pub fn insert(&mut self, value: f64) {
+        self.seen += 1;
+        if self.sample.len() < RESERVOIR_SIZE {
+            self.sample.push(value);
+        } else {
+            let index = self.random() % self.seen;
+            if let Some(slot) = self.sample.get_mut(index as usize) {
+                *slot = value;
+            }
+        }
+    }
+
+    /// Combines the samples of two streams, taking values from each in
+    /// proportion to the number of values it has seen.
+    pub fn merge(&mut self, other: &Reservoir) {
+        let seen = self.seen + other.seen;
+        if self.sample.len() + other.sample.len() <= RESERVOIR_SIZE {
+            self.sample.extend(&other.sample);
+            self.seen = seen;
+            return;
+        }
+
+        let from_self = (RESERVOIR_SIZE as f64 * self.seen as f64 / seen as f64).round() as usize;
+        let from_self = from_self.min(self.sample.len());
+        let from_other = (RESERVOIR_SIZE - from_self).min(other.sample.len());
+
+        let mut sample = std::mem::take(&mut self.sample);
+        self.shuffle(&mut sample);
+        sample.truncate(from_self);
+        let mut other_sample = other.sample.clone();
+        self.shuffle(&mut other_sample);
+        sample.extend_from_slice(&other_sample[..from_other]);
+
+        self.sample = sample;
+        self.seen = seen;
+    }
+
+    fn shuffle(&mut self, values: &mut [f64]) {
+        for i in (1..values.len()).rev() {
+            let j = self.random() % (i as u64 + 1);
+            values.swap(i, j as usize);
+        }
+    }
+
+    pub fn into_sample(self) -> Vec<f64> {
+        self.sample
+    }
+}
+
+/// Returns the bounds of an equi-depth histogram of `values` with up to
+/// `buckets` buckets, i.e. `buckets + 1` values from the minimum to the maximum
+/// such that each bucket holds the same number of values.
+pub fn equi_depth(mut values: Vec<f64>, buckets: usize) -> Vec<f64> {
+    if values.is_empty() || buckets == 0 {
+        return Vec::new();
+    }
+    values.sort_unstable_by(f64::total_cmp);
+    let buckets = buckets.min(values.len());
+    let mut bounds = (0..buckets)
+        .map(|i| values[i * values.len() / buckets])
+        .collect::<Vec<_>>();
+    bounds.push(values[values.len() - 1]);
+    bounds
+}
+
+/// Fraction of the values of a histogram up to `x`, assuming values are spread
+/// uniformly within each bucket.
+fn cumulative(bounds: &[f64], x: f64) -> f64 {
+    let buckets = bounds.len() - 1;
+    if x < bounds[0] {
+        return 0.0;
+    }
+    if x >= bounds[buckets] {
+        return 1.0;
+    }
+    let i = bounds
+        .partition_point(|&bound| bound <= x)
+        .saturating_sub(1);
+    let (low, high) = (bounds[i], bounds[i + 1]);
+    let within = match high > low {
+        true => (x - low) / (high - low),
+        false => 1.0,
+    };
+    (i as f64 + within) / buckets as f64
+}
+
+/// Combines the histograms of two disjoint sets of `count` and `other_count`
+/// values into one with as many buckets as the finer of the two.
+pub fn merge_histograms(bounds: &[f64], count: u64, other: &[f64], other_count: u64) -> Vec<f64> {
+    if bounds.len() < 2 || other.len() < 2 {
+        return match bounds.len() < 2 {
+            true => other.to_vec(),
+            false => bounds.to_vec(),
+        };
+    }
+
+    let total = (count + other_count) as f64;
+    let combined = |x: f64| {
+        (cumulative(bounds, x) * count as f64 + cumulative(other, x) * other_count as f64) / total
+    };
+    let (min, max) = (
+        bounds[0].min(other[0]),
+        bounds[bounds.len() - 1].max(other[other.len() - 1]),
+    );
+    let buckets = (bounds.len() - 1).max(other.len() - 1);
+
+    let mut merged = vec![min];
+    for i in 1..buckets {
+        // bisect for the value below which a fraction `i / buckets` of both lies
+        let target = i as f64 / buckets as f64;
+        let (mut low, mut high) = (min, max);
+        for _ in 0..64 {
+            let mid = low + (high - low) / 2.0;
+            match combined(mid) < target {
+                true => low = mid,
+                false => high = mid,
+            }
+        }
+        merged.push(high);
+    }
+    merged.push(max);
+    merged
+}
@@ -0,0 +1,561 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use parquet::{
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+};
+
+use crate::{
+    error::{Error, Position, Result},
+    property::{format_date, format_datetime},
+};
+
+/// A data file together with the name of the entity it holds, e.g.
+/// `person_knows_person` for `dynamic/person_knows_person_0_0.csv` and
+/// `Person_knows_Person` for `dynamic/Person_knows_Person/part-00000-....csv`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InputFile {
+    pub entity: String,
+    pub path: PathBuf,
+}
+
+/// Lists the data files below `csv_dir`.
+///
+/// Both the flat layout of the Hadoop datagen (`static/place_0_0.csv`) and the
+/// per-entity directories of the Spark datagen (`static/Place/part-*.csv`) are
+/// recognised. If `csv_dir` holds an `initial_snapshot` directory, as in
+/// `graphs/csv/bi/composite-merged-fk`, the snapshot is read.
+pub fn discover(csv_dir: &Path) -> Result<Vec<InputFile>> {
+    let snapshot = csv_dir.join("initial_snapshot");
+    let root = if snapshot.is_dir() {
+        snapshot
+    } else {
+        csv_dir.to_owned()
+    };
+
+    let mut files = Vec::new();
+    for dir in ["static", "dynamic"] {
+        for path in read_dir(&root.join(dir))? {
+            if path.is_dir() {
+                let entity = match path.file_name().and_then(|name| name.to_str()) {
+                    Some(entity) => entity.to_owned(),
+                    None => return Err(Error::FileName { path }),
+                };
+                for part in read_dir(&path)? {
+                    if part.is_file() {
+                        files.push(InputFile {
+                            entity: entity.clone(),
+                            path: part,
+                        });
+                    }
+                }
+            } else {
+                let entity =
+                    entity_name(&path).ok_or_else(|| Error::FileName { path: path.clone() })?;
+                files.push(InputFile {
+                    entity: entity.to_owned(),
+                    path,
+                });
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Lists the data files of the insert or delete batches of the BI workload in
+/// `dir`, e.g. `inserts/dynamic/Comment/batch_id=2012-09-13/part-*.csv` for
+/// `inserts`, or only those of `batch` if given. Files directly below the
+/// entity directory belong to no batch. A missing `dir` holds none.
+pub fn discover_batches(dir: &Path, batch: Option<&str>) -> Result<Vec<InputFile>> {
+    let mut files = Vec::new();
+    for kind in ["static", "dynamic"] {
+        if !dir.join(kind).is_dir() {
+            continue;
+        }
+        for path in read_dir(&dir.join(kind))? {
+            let entity = match path.file_name().and_then(|name| name.to_str()) {
+                Some(entity) if path.is_dir() => entity.to_owned(),
+                _ => return Err(Error::FileName { path }),
+            };
+            for batch_dir in read_dir(&path)? {
+                if batch_dir.is_file() {
+                    if batch.is_none() {
+                        files.push(InputFile {
+                            entity: entity.clone(),
+                            path: batch_dir,
+                        });
+                    }
+                    continue;
+                }
+                let id = batch_dir
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix("batch_id="));
+                match id {
+                    Some(id) if batch.is_none_or(|batch| batch == id) => (),
+                    Some(_) => continue,
+                    None => return Err(Error::FileName { path: batch_dir }),
+                }
+                for part in read_dir(&batch_dir)? {
+                    if part.is_file() {
+                        files.push(InputFile {
+                            entity: entity.clone(),
+                            path: part,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Lists the entries of `dir`, leaving out hidden files and Spark's `_SUCCESS`
+/// markers and `.crc` checksums.
+fn read_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let io_error = |source| Error::Io {
+        path: dir.to_owned(),
+        source,
+    };
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        let hidden = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .is_some_and(|name| name.starts_with('.') || name.starts_with('_'));
+        if !hidden {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Strips the extensions, including any compression suffix, and the `_<n>_<n>`
+/// partition suffix from the file name, e.g. `person_knows_person_0_0.csv` and
+/// `person_knows_person_0_0.csv.gz` become `person_knows_person`.
+fn entity_name(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    let mut name = name.split_once('.').map_or(name, |(stem, _)| stem);
+    while let Some((head, tail)) = name.rsplit_once('_') {
+        if tail.is_empty() || !tail.bytes().all(|b| b.is_ascii_digit()) {
+            break;
+        }
+        name = head;
+    }
+    Some(name)
+}
+
+/// A record read from `path`, with access to its fields by header index.
+pub struct Row<'a> {
+    pub path: &'a Path,
+    pub header: &'a csv::StringRecord,
+    pub position: Position,
+    pub record: csv::StringRecord,
+}
+
+impl<'a> Row<'a> {
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn column(&self, index: usize) -> String {
+        self.header.get(index).unwrap_or_default().to_owned()
+    }
+
+    pub fn field(&self, index: usize) -> Result<&str> {
+        self.record.get(index).ok_or_else(|| Error::ShortRow {
+            path: self.path.to_owned(),
+            position: self.position(),
+            column: self.column(index),
+            len: self.record.len(),
+        })
+    }
+
+    pub fn id(&self, index: usize) -> Result<u64> {
+        let value = self.field(index)?;
+        value.parse::<u64>().map_err(|_| Error::InvalidId {
+            path: self.path.to_owned(),
+            position: self.position(),
+            column: self.column(index),
+            value: value.to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "gz" | "gzip" => Compression::Gzip,
+            "zst" | "zstd" => Compression::Zstd,
+            "bz2" => Compression::Bzip2,
+            _ => return None,
+        })
+    }
+
+    fn from_magic(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if bytes.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Opens `path` for reading, decompressing it on the fly if its extension or
+/// its first bytes indicate gzip, zstd or bzip2 compression.
+pub fn open(path: &Path) -> Result<Box<dyn Read + Send>> {
+    let io_error = |source| Error::Io {
+        path: path.to_owned(),
+        source,
+    };
+    let mut file = BufReader::new(File::open(path).map_err(io_error)?);
+    let compression = match Compression::from_extension(path) {
+        Some(compression) => compression,
+        None => Compression::from_magic(file.fill_buf().map_err(io_error)?),
+    };
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(file)),
+        Compression::Zstd => Box::new(zstd::Decoder::with_buffer(file).map_err(io_error)?),
+        Compression::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(file)),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Parquet,
+}
+
+impl Format {
+    /// Parquet files are recognised by their extension or their `PAR1` magic.
+    fn detect(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        if extension.is_some_and(|ext| ext.eq_ignore_ascii_case("parquet")) {
+            return Ok(Format::Parquet);
+        }
+        let mut magic = [0; 4];
+        let mut file = File::open(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        match file.read_exact(&mut magic) {
+            Ok(()) if &magic == b"PAR1" => Ok(Format::Parquet),
+            _ => Ok(Format::Csv),
+        }
+    }
+}
+
+/// A record and its location in the file.
+pub type Record = (Position, csv::StringRecord);
+
+pub type Records = Box<dyn Iterator<Item = Result<Record>> + Send>;
+
+/// The header of an input file and an iterator over its records.
+///
+/// Parquet rows are converted to the same string records as CSV lines so both
+/// are imported by the same code.
+pub struct Source {
+    pub header: csv::StringRecord,
+    pub records: Records,
+}
+
+impl Source {
+    pub fn open(path: &Path) -> Result<Self> {
+        match Format::detect(path)? {
+            Format::Csv => {
+                let (rdr, header) = open_csv(path)?;
+                Ok(Source {
+                    header,
+                    records: csv_records(path.to_owned(), rdr),
+                })
+            }
+            Format::Parquet => {
+                let reader = open_parquet(path)?;
+                Ok(Source {
+                    header: parquet_header(&reader),
+                    records: parquet_records(path.to_owned(), reader),
+                })
+            }
+        }
+    }
+}
+
+/// Reads only the header of the file at `path`.
+pub fn read_header(path: &Path) -> Result<csv::StringRecord> {
+    match Format::detect(path)? {
+        Format::Csv => open_csv(path).map(|(_, header)| header),
+        Format::Parquet => open_parquet(path).map(|reader| parquet_header(&reader)),
+    }
+}
+
+type CsvReader = csv::Reader<Box<dyn Read + Send>>;
+
+fn open_csv(path: &Path) -> Result<(CsvReader, csv::StringRecord)> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .delimiter(b'|')
+        .flexible(true)
+        .from_reader(open(path)?);
+
+    let header = rdr
+        .headers()
+        .map_err(|source| Error::Csv {
+            path: path.to_owned(),
+            source,
+        })?
+        .clone();
+
+    Ok((rdr, header))
+}
+
+fn csv_records(path: PathBuf, rdr: CsvReader) -> Records {
+    Box::new(rdr.into_records().map(move |record| {
+        record
+            .map(|record| {
+                let position = record
+                    .position()
+                    .map_or(Position::Line { line: 0, byte: 0 }, Position::from);
+                (position, record)
+            })
+            .map_err(|source| Error::Csv {
+                path: path.clone(),
+                source,
+            })
+    }))
+}
+
+fn open_parquet(path: &Path) -> Result<SerializedFileReader<File>> {
+    let file = File::open(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    SerializedFileReader::new(file).map_err(|source| Error::Parquet {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn parquet_header(reader: &SerializedFileReader<File>) -> csv::StringRecord {
+    reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|field| field.name())
+        .collect()
+}
+
+/// Formats a Parquet value the way the datagen writes it to CSV: strings
+/// unquoted, nulls empty, dates and timestamps in ISO 8601 and lists joined by
+/// `;`.
+fn parquet_field(field: &Field) -> String {
+    match field {
+        Field::Null => String::new(),
+        Field::Date(days) => format_date(i64::from(*days)),
+        Field::TimestampMillis(millis) => format_datetime(*millis),
+        Field::TimestampMicros(micros) => format_datetime(micros.div_euclid(1000)),
+        Field::Str(value) => value.clone(),
+        Field::Bytes(value) => String::from_utf8_lossy(value.data()).into_owned(),
+        Field::ListInternal(list) => list
+            .elements()
+            .iter()
+            .map(parquet_field)
+            .collect::<Vec<_>>()
+            .join(";"),
+        _ => field.to_string(),
+    }
+}
+
+fn parquet_records(path: PathBuf, reader: SerializedFileReader<File>) -> Records {
+    Box::new(reader.into_iter().enumerate().map(move |(i, row)| {
+        row.map(|row| {
+            let record = row
+                .get_column_iter()
+                .map(|(_, field)| parquet_field(field))
+                .collect();
+            (Position::Row(i as u64 + 1), record)
+        })
+        .map_err(|source| Error::Parquet {
+            path: path.clone(),
+            source,
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, sync::Arc};
+
+    use parquet::{
+        data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type},
+        file::{properties::WriterProperties, writer::SerializedFileWriter},
+        schema::parser::parse_message_type,
+    };
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    const CSV: &str = "id|name\n0|Go\n1|Rust\n";
+
+    fn read_to_string(path: &Path) -> String {
+        let mut contents = String::new();
+        open(path).unwrap().read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn bzip2(data: &[u8]) -> Vec<u8> {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decompresses_by_extension_or_magic_bytes() {
+        let dir = TempDir::new("compression");
+        let compressed = [
+            ("gz", Compression::Gzip, gzip(CSV.as_bytes())),
+            (
+                "zst",
+                Compression::Zstd,
+                zstd::encode_all(CSV.as_bytes(), 0).unwrap(),
+            ),
+            ("bz2", Compression::Bzip2, bzip2(CSV.as_bytes())),
+        ];
+        for (extension, compression, data) in compressed {
+            assert_eq!(Compression::from_magic(&data), compression);
+            let name = format!("tag_0_0.csv.{}", extension);
+            assert_eq!(read_to_string(&dir.write(&name, &data)), CSV);
+            let name = format!("{}/tag_0_0.csv", extension);
+            assert_eq!(read_to_string(&dir.write(&name, &data)), CSV);
+        }
+        assert_eq!(read_to_string(&dir.write("tag_0_0.csv", CSV)), CSV);
+    }
+
+    #[test]
+    fn decompresses_concatenated_streams() {
+        let dir = TempDir::new("concatenated");
+        let (head, tail) = CSV.split_at(10);
+        let data = [gzip(head.as_bytes()), gzip(tail.as_bytes())].concat();
+        assert_eq!(read_to_string(&dir.write("tag_0_0.csv.gz", data)), CSV);
+        let data = [bzip2(head.as_bytes()), bzip2(tail.as_bytes())].concat();
+        assert_eq!(read_to_string(&dir.write("tag_0_0.csv.bz2", data)), CSV);
+    }
+
+    #[test]
+    fn formats_parquet_fields_like_csv() {
+        assert_eq!(parquet_field(&Field::Null), "");
+        assert_eq!(parquet_field(&Field::Str("Firefox".to_owned())), "Firefox");
+        assert_eq!(parquet_field(&Field::Long(42)), "42");
+        assert_eq!(parquet_field(&Field::Date(7_276)), "1989-12-03");
+        let datetime = "2010-05-01T00:00:00.123+00:00";
+        assert_eq!(
+            parquet_field(&Field::TimestampMillis(1_272_672_000_123)),
+            datetime
+        );
+        assert_eq!(
+            parquet_field(&Field::TimestampMicros(1_272_672_000_123_456)),
+            datetime
+        );
+    }
+
+    /// Writes the rows of `PARQUET_CSV` as a Parquet file with a nullable
+    /// string, a timestamp and a date column.
+    fn write_parquet(path: &Path) {
+        let schema = parse_message_type(
+            "message person {
+                REQUIRED INT64 id;
+                OPTIONAL BINARY firstName (UTF8);
+                REQUIRED INT64 creationDate (TIMESTAMP(MILLIS, true));
+                REQUIRED INT32 birthday (DATE);
+            }",
+        )
+        .unwrap();
+        let properties = Arc::new(WriterProperties::builder().build());
+        let file = File::create(path).unwrap();
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), properties).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[10, 11], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from("Ana")], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1_272_672_000_123, 0], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int32Type>()
+            .write_batch(&[7_276, -1], None, None)
+            .unwrap();
+        column.close().unwrap();
+
+        row_group.close().unwrap();
+        writer.close().unwrap();
+    }
+
+    const PARQUET_CSV: &str = "id|firstName|creationDate|birthday\n\
+        10|Ana|2010-05-01T00:00:00.123+00:00|1989-12-03\n\
+        11||1970-01-01T00:00:00.000+00:00|1969-12-31\n";
+
+    fn read_source(path: &Path) -> (csv::StringRecord, Vec<csv::StringRecord>) {
+        let Source { header, records } = Source::open(path).unwrap();
+        let records = records.map(|record| record.unwrap().1).collect();
+        (header, records)
+    }
+
+    #[test]
+    fn reads_parquet_like_the_equivalent_csv() {
+        let dir = TempDir::new("parquet");
+        let expected = read_source(&dir.write("person_0_0.csv", PARQUET_CSV));
+        assert_eq!(expected.1.len(), 2);
+
+        let path = dir.path().join("person_0_0.parquet");
+        write_parquet(&path);
+        assert_eq!(read_source(&path), expected);
+        assert_eq!(read_header(&path).unwrap(), expected.0);
+        // recognised by its magic bytes as well
+        let renamed = dir.path().join("part-00000");
+        std::fs::rename(&path, &renamed).unwrap();
+        assert_eq!(read_source(&renamed), expected);
+    }
+}
@@ -0,0 +1,37 @@
+//! Computes vertex and edge cardinalities of LDBC SNB datasets for cost-based
+//! query optimizers.
+//!
+//! A [`Context`] imports the CSV or Parquet files of a dataset and yields its
+//! [`Statistics`], which can be saved to and loaded from JSON. An [`Estimator`]
+//! derives the cardinalities of path [`Pattern`]s from them.
+
+mod context;
+mod cycles;
+mod error;
+mod estimator;
+mod frequent;
+mod header;
+mod hierarchy;
+mod histogram;
+mod input;
+mod property;
+mod schema;
+mod sketch;
+mod statistics;
+#[cfg(test)]
+mod testing;
+
+pub use context::{Context, ContextBuilder, Granularity, OnError, Progress, MAX_MOST_COMMON};
+pub use error::{Error, Position, Result, SkipReason};
+pub use estimator::{Direction, Estimator, Pattern, Step};
+pub use input::{discover, discover_batches, InputFile};
+pub use property::PropertyType;
+pub use schema::{EdgeMapping, Entity, ForeignKey, Schema, VertexMapping};
+pub use sketch::HyperLogLog;
+pub use statistics::{
+    CharacteristicSet, CharacteristicSets, ColumnStatistics, CommonValue, CycleCounts,
+    CycleStatistics, DegreeDistribution, DegreeDistributions, DistinctCounts, DistinctEndpoints,
+    Distribution, EdgeCardinality, EdgePropertyMap, EdgeSeries, EndpointSketches, Path2Cardinality,
+    PropertyMap, PropertyStatistics, Sketches, SkippedRows, Statistics, TimeSeries,
+    VertexCardinality, VertexSeries,
+};
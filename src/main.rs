@@ -1,292 +1,344 @@
-use std::{collections::HashMap, sync::mpsc::sync_channel, io::Write};
+use std::path::{Path, PathBuf};
 
-use clap::Parser;
+use clap::{AppSettings, Args, Parser, Subcommand};
 
-use serde::{Serialize, Deserialize};
+use ldbc_stat_gen::{
+    Context, ContextBuilder, Estimator, Granularity, OnError, Pattern, Progress, Result, Schema,
+    SkipReason, Statistics, MAX_MOST_COMMON,
+};
 
+/// Counts the vertices and edges of an LDBC SNB dataset in `csv_dir`, or runs
+/// one of the subcommands.
 #[derive(Parser, Debug)]
+#[clap(
+    setting = AppSettings::ArgsNegateSubcommands,
+    setting = AppSettings::SubcommandsNegateReqs
+)]
 struct Config {
-    csv_dir: String,
-    output_file: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(required = true)]
+    csv_dir: Option<String>,
+    #[clap(required = true)]
+    output_file: Option<String>,
+    #[clap(flatten)]
+    counting: Counting,
 }
 
-type VertexCardinality = HashMap<String, f64>;
-type EdgeCardinality =
-    HashMap<String, HashMap<String, HashMap<String, f64>>>;
-
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct Statistics {
-    vertex_cardinality: VertexCardinality,
-    edge_cardinality: EdgeCardinality,
+/// Options of importing data files, shared by counting and updating.
+#[derive(Args, Debug)]
+struct Importing {
+    /// Schema file (TOML, JSON or YAML) mapping file names to labels
+    #[clap(long)]
+    schema: Option<PathBuf>,
+    /// What to do with malformed rows
+    #[clap(long, arg_enum, default_value = "fail")]
+    on_error: OnError,
+    /// Number of import workers, defaults to the number of CPUs
+    #[clap(long)]
+    jobs: Option<usize>,
+    /// Estimate distinct counts with HyperLogLog sketches, using bounded memory
+    /// but leaving out degree distributions
+    #[clap(long)]
+    approx: bool,
 }
 
-#[derive(Debug, Default)]
-struct Context {
-    // vertex_cardinality: VertexCardinality,
-    // edge_cardinality: EdgeCardinality,
-    statistics: Statistics,
-
-    place: HashMap<u64, String>,
-    organisation: HashMap<u64, String>,
+impl Importing {
+    fn builder(&self) -> Result<ContextBuilder> {
+        let mut builder = Context::builder()
+            .on_error(self.on_error)
+            .approx(self.approx)
+            .progress(|progress| match progress {
+                Progress::Import(path) => println!("import {:?}", path.as_os_str()),
+                Progress::Ignore(path) => println!("ignore {:?}", path.as_os_str()),
+            });
+        if let Some(path) = &self.schema {
+            builder = builder.schema(Schema::load(path)?);
+        }
+        if let Some(jobs) = self.jobs {
+            builder = builder.jobs(jobs);
+        }
+        Ok(builder)
+    }
 }
 
-impl Context {
-    async fn import_vertex(&mut self, path: std::path::PathBuf, label_name: String) {
-        let mut rdr = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .delimiter(b'|')
-            .from_path(path)
-            .unwrap();
-
-        let header: Vec<String> = rdr.headers().unwrap().deserialize(None).unwrap();
-
-        // add vertex label
-
-        let mut label_index = None;
-        let id_index = 0;
-
-        for (i, s) in header.into_iter().enumerate() {
-            let v = s.split(':').collect::<Vec<_>>();
-            let prop_type = v[1];
+/// Options of counting the vertices and edges of data files.
+#[derive(Args, Debug)]
+struct Counting {
+    #[clap(flatten)]
+    importing: Importing,
+    /// Count paths of two edges, from a sample of the vertices with --approx
+    #[clap(long)]
+    paths: bool,
+    /// Count triangles and 4-cycles of a homogeneous triple of a concrete label
+    /// such as Person-KNOWS-Person or City-IS_PART_OF-City; may be repeated
+    #[clap(
+        long,
+        value_name = "TRIPLE",
+        parse(try_from_str = parse_triple),
+        multiple_occurrences(true)
+    )]
+    cycles: Vec<(String, String)>,
+    /// Count vertices and edges per day, month or year of their creationDate
+    #[clap(long, arg_enum)]
+    time_series: Option<Granularity>,
+    /// Collect statistics of the property columns, which in exact mode keeps
+    /// the distinct values of every column in memory
+    #[clap(long)]
+    properties: bool,
+    /// Number of buckets of the histograms of numeric and temporal properties
+    /// with --properties
+    #[clap(long, default_value = "32")]
+    histogram_buckets: usize,
+    /// Number of most common values listed per string property with
+    /// --properties, at most 256
+    #[clap(long, default_value = "10", parse(try_from_str = parse_most_common))]
+    most_common: usize,
+}
 
-            match prop_type {
-                "LABEL" => {
-                    assert!(label_index.replace(i).is_none());
-                }
-                _ if prop_type.starts_with("ID") => {
-                    assert_eq!(id_index, i);
-                }
-                _ => (),
-            }
+impl Counting {
+    fn builder(&self) -> Result<ContextBuilder> {
+        let mut builder = self
+            .importing
+            .builder()?
+            .paths(self.paths)
+            .properties(self.properties)
+            .histogram_buckets(self.histogram_buckets)
+            .most_common(self.most_common);
+        for (label, edge_label) in &self.cycles {
+            builder = builder.cycles(label, edge_label);
         }
-
-        let (tx, rx) = sync_channel::<Vec<String>>(1024);
-
-        tokio::spawn(async move {
-            for record in rdr.into_records() {
-                let record: Vec<String> = record.unwrap().deserialize(None).unwrap();
-                tx.send(record).unwrap();
-            }
-        });
-
-        for record in rx {
-            if let Some(label_index) = label_index {
-                match record[label_index].as_str() {
-                    "City" | "Country" | "Continent" => assert!(self
-                        .place
-                        .insert(
-                            record[id_index].parse::<u64>().unwrap(),
-                            record[label_index].clone()
-                        )
-                        .is_none()),
-                    "University" | "Company" => assert!(self
-                        .organisation
-                        .insert(
-                            record[id_index].parse::<u64>().unwrap(),
-                            record[label_index].clone()
-                        )
-                        .is_none()),
-                    _ => (),
-                };
-            }
-            let label =
-                label_index.map_or_else(|| label_name.clone(), |index| record[index].clone());
-
-            *self.statistics.vertex_cardinality.entry(label).or_insert(0.0) += 1.0;
-            *self.statistics.vertex_cardinality.entry("".to_owned()).or_insert(0.0) += 1.0;
+        if let Some(granularity) = self.time_series {
+            builder = builder.time_series(granularity);
         }
+        Ok(builder)
     }
+}
 
-    async fn import_edge(
-        &mut self,
-        path: std::path::PathBuf,
-        (src_label, edge_label, dst_label): (String, String, String),
-    ) {
-        let mut rdr = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .delimiter(b'|')
-            .from_path(path)
-            .unwrap();
-
-        let header: Vec<String> = rdr.headers().unwrap().deserialize(None).unwrap();
-
-        let (src_id_index, dst_id_index) = (0, 1);
-
-        let (mut src_label_map, mut dst_label_map) = (None, None);
-
-        for (i, s) in header.into_iter().enumerate() {
-            let v = s.split(':').collect::<Vec<_>>();
-            let prop_type = v[1];
+fn parse_most_common(s: &str) -> std::result::Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(k) if k <= MAX_MOST_COMMON => Ok(k),
+        Ok(_) => Err(format!(
+            "at most {} values are tracked per property",
+            MAX_MOST_COMMON
+        )),
+        Err(err) => Err(err.to_string()),
+    }
+}
 
-            match prop_type {
-                _ if prop_type.starts_with("START_ID") => {
-                    assert_eq!(src_id_index, i);
-                    if src_label == "Organisation" {
-                        assert!(src_label_map.replace(&self.organisation).is_none());
-                    } else if src_label == "Place" {
-                        assert!(src_label_map.replace(&self.place).is_none());
-                    }
-                }
-                _ if prop_type.starts_with("END_ID") => {
-                    assert_eq!(dst_id_index, i);
-                    if dst_label == "Organisation" {
-                        assert!(dst_label_map.replace(&self.organisation).is_none());
-                    } else if dst_label == "Place" {
-                        assert!(dst_label_map.replace(&self.place).is_none());
-                    }
-                }
-                _ => (),
-            }
+/// Parses `Label-EDGE-Label` into the vertex and edge label.
+fn parse_triple(s: &str) -> std::result::Result<(String, String), String> {
+    match s.split('-').collect::<Vec<_>>()[..] {
+        [src, edge, dst] if src == dst && !src.is_empty() && !edge.is_empty() => {
+            Ok((src.to_owned(), edge.to_owned()))
         }
+        [_, _, _] => Err("source and destination label must be the same".to_owned()),
+        _ => Err("expected LABEL-EDGE-LABEL".to_owned()),
+    }
+}
 
-        let (tx, rx) = sync_channel::<Vec<String>>(1024);
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Sum the statistics of several output files, e.g. of separately counted shards
+    Merge {
+        output_file: String,
+        #[clap(required = true)]
+        input_files: Vec<String>,
+    },
+    /// Estimate the cardinalities of path patterns such as
+    /// `(Person)-[KNOWS]->(Person)-[IS_LOCATED_IN]->(City)`
+    Estimate {
+        statistics_file: String,
+        #[clap(required = true)]
+        patterns: Vec<String>,
+    },
+    /// Apply the insert and then the delete batches of the BI workload in
+    /// `csv_dir` to the statistics of its initial snapshot. Only cardinalities
+    /// and distinct endpoints are updated, the sections that need the vertices
+    /// behind them are dropped
+    Update {
+        statistics_file: String,
+        csv_dir: String,
+        output_file: String,
+        /// Only apply the batch with this id, e.g. 2012-09-13
+        #[clap(long)]
+        batch: Option<String>,
+        #[clap(flatten)]
+        importing: Importing,
+    },
+}
 
-        tokio::spawn(async move {
-            for record in rdr.into_records() {
-                let record: Vec<String> = record.unwrap().deserialize(None).unwrap();
-                tx.send(record).unwrap();
-            }
-        });
+async fn run(config: Config) -> Result<()> {
+    match &config.command {
+        Some(Command::Merge {
+            output_file,
+            input_files,
+        }) => return merge(output_file, input_files),
+        Some(Command::Estimate {
+            statistics_file,
+            patterns,
+        }) => return estimate(statistics_file, patterns),
+        Some(Command::Update {
+            statistics_file,
+            csv_dir,
+            output_file,
+            batch,
+            importing,
+        }) => {
+            return update(
+                statistics_file,
+                Path::new(csv_dir),
+                output_file,
+                batch.as_deref(),
+                importing,
+            )
+            .await
+        }
+        None => (),
+    }
+    let (csv_dir, output_file) = match (config.csv_dir, config.output_file) {
+        (Some(csv_dir), Some(output_file)) => (csv_dir, output_file),
+        _ => unreachable!("clap requires the arguments without a subcommand"),
+    };
 
-        for record in rx {
-            let src_id = record[src_id_index].parse::<u64>().unwrap();
-            let dst_id = record[dst_id_index].parse::<u64>().unwrap();
-            let src_label = src_label_map
-                .map_or_else(|| src_label.clone(), |m| m.get(&src_id).unwrap().clone());
-            let dst_label = dst_label_map
-                .map_or_else(|| dst_label.clone(), |m| m.get(&dst_id).unwrap().clone());
+    let mut context = config.counting.builder()?.build();
+    context.import_dir(Path::new(&csv_dir)).await?;
+    let statistics = context.into_statistics();
+    report_skipped(&statistics);
+    report_unmatched_cycles(&config.counting.cycles, &statistics);
+    statistics.save(Path::new(&output_file))
+}
 
-            for src_key in [src_label, "".to_owned()] {
-                let src_entry = self
-                    .statistics.edge_cardinality
-                    .entry(src_key)
-                    .or_insert_with(HashMap::new);
-                for edge_key in [edge_label.clone(), "".to_owned()] {
-                    let edge_entry = src_entry.entry(edge_key).or_insert_with(HashMap::new);
-                    for dst_key in [dst_label.clone(), "".to_owned()] {
-                        let dst_entry = edge_entry.entry(dst_key).or_insert(0.0);
-                        *dst_entry += 1.0;
-                    }
-                }
+fn report_skipped(statistics: &Statistics) {
+    for (file, reasons) in &statistics.skipped_rows {
+        for (reason, count) in reasons {
+            match reason {
+                SkipReason::UnknownForeignKey => eprintln!(
+                    "left out {} foreign-key edges in {}: {:?}",
+                    count, file, reason
+                ),
+                _ => eprintln!("skipped {} rows in {}: {:?}", count, file, reason),
             }
         }
     }
 }
 
-#[derive(Debug)]
-enum LabelName {
-    Vertex(String),
-    Edge(String, String, String),
+/// Warns about the triples of `--cycles` without edges, which only match
+/// concrete labels such as City rather than their parent Place.
+fn report_unmatched_cycles(cycles: &[(String, String)], statistics: &Statistics) {
+    for (label, edge_label) in cycles {
+        let matched = statistics
+            .cycles
+            .get(label)
+            .and_then(|edges| edges.get(edge_label))
+            .is_some_and(|dsts| dsts.contains_key(label));
+        if matched {
+            continue;
+        }
+        let mut children = statistics
+            .parent_labels
+            .iter()
+            .filter(|(_, parent)| *parent == label)
+            .map(|(child, _)| child.as_str())
+            .collect::<Vec<_>>();
+        children.sort_unstable();
+        match children.is_empty() {
+            true => eprintln!(
+                "warning: no {}-{}-{} edges to count cycles of",
+                label, edge_label, label
+            ),
+            false => eprintln!(
+                "warning: no cycles counted for {}-{}-{}, as {} is the parent of the labels {}; \
+                 --cycles takes a concrete label",
+                label,
+                edge_label,
+                label,
+                label,
+                children.join(", ")
+            ),
+        }
+    }
 }
 
-fn resolve_file_name(path: &std::path::Path) -> LabelName {
-    let (src_name, edge_name, dst_name) = {
-        let v = path
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .split('_')
-            .collect::<Vec<_>>();
-        (v[0], v[1], v[2])
-    };
+async fn update(
+    statistics_file: &str,
+    csv_dir: &Path,
+    output_file: &str,
+    batch: Option<&str>,
+    importing: &Importing,
+) -> Result<()> {
+    let mut statistics = Statistics::load(Path::new(statistics_file))?;
+    let dropped = statistics.derived_sections();
+    if !dropped.is_empty() {
+        eprintln!(
+            "warning: the update drops {}, which only counting the updated graph restores",
+            dropped.join(", ")
+        );
+    }
 
-    let resolve_edge_name = |name| {
-        Some(match name {
-            "isPartOf" => "IS_PART_OF",
-            "isSubclassOf" => "IS_SUBCLASS_OF",
-            "isLocatedIn" => "IS_LOCATED_IN",
-            "hasType" => "HAS_TYPE",
-            "hasCreator" => "HAS_CREATOR",
-            "replyOf" => "REPLY_OF",
-            "containerOf" => "CONTAINER_OF",
-            "hasMember" => "HAS_MEMBER",
-            "hasModerator" => "HAS_MODERATOR",
-            "hasTag" => "HAS_TAG",
-            "hasInterest" => "HAS_INTEREST",
-            "knows" => "KNOWS",
-            "likes" => "LIKES",
-            "studyAt" => "STUDY_AT",
-            "workAt" => "WORK_AT",
-            _ => return None,
-        })
+    let mut inserts = importing.builder()?.build();
+    inserts.register_labels(csv_dir).await?;
+    inserts
+        .import_batches(&csv_dir.join("inserts"), batch)
+        .await?;
+    let inserted = inserts.into_statistics();
+    report_skipped(&inserted);
+
+    let mut deletes = importing.builder()?.build();
+    deletes.register_labels(csv_dir).await?;
+    deletes
+        .import_batches(&csv_dir.join("deletes"), batch)
+        .await?;
+    let deleted = deletes.into_statistics();
+    report_skipped(&deleted);
+
+    let triples = |statistics: &Statistics| {
+        statistics
+            .distinct_endpoints
+            .values()
+            .flat_map(|edges| edges.values())
+            .map(|dsts| dsts.len())
+            .sum::<usize>()
     };
+    let counted = triples(&statistics);
+    statistics.apply_inserts(inserted);
+    let kept = triples(&statistics);
+    if kept < counted {
+        eprintln!(
+            "warning: the update drops the exact distinct_endpoints of {} triples with inserted \
+             edges, which --approx would have updated",
+            counted - kept
+        );
+    }
+    statistics.remove(deleted);
+    statistics.save(Path::new(output_file))
+}
 
-    let resolve_vertex_name = |name| {
-        Some(match name {
-            "place" => "Place",
-            "organisation" => "Organisation",
-            "tagclass" => "TagClass",
-            "tag" => "Tag",
-            "comment" => "Comment",
-            "forum" => "Forum",
-            "person" => "Person",
-            "post" => "Post",
-            _ => return None,
-        })
-    };
+fn merge(output_file: &str, input_files: &[String]) -> Result<()> {
+    let mut statistics = Statistics::default();
+    for input_file in input_files {
+        println!("merge {:?}", input_file);
+        statistics.merge(Statistics::load(Path::new(input_file))?);
+    }
+    statistics.save(Path::new(output_file))
+}
 
-    match (
-        resolve_vertex_name(src_name),
-        resolve_edge_name(edge_name),
-        resolve_vertex_name(dst_name),
-    ) {
-        (Some(src_name), Some(edge_name), Some(dst_name)) => LabelName::Edge(
-            src_name.to_string(),
-            edge_name.to_string(),
-            dst_name.to_string(),
-        ),
-        (Some(vertex_name), None, None) => LabelName::Vertex(vertex_name.to_string()),
-        _ => panic!(
-            "illegal label name {:?} {:?} {:?}",
-            src_name, edge_name, dst_name
-        ),
+fn estimate(statistics_file: &str, patterns: &[String]) -> Result<()> {
+    let statistics = Statistics::load(Path::new(statistics_file))?;
+    let estimator = Estimator::new(&statistics);
+    for pattern in patterns {
+        let pattern = pattern.parse::<Pattern>()?;
+        println!("{}\t{}", pattern, estimator.estimate(&pattern)?);
     }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() {
     let config = Config::parse();
 
-    let mut paths = std::fs::read_dir(std::path::Path::new(&config.csv_dir).join("static"))
-        .unwrap()
-        .chain(std::fs::read_dir(std::path::Path::new(&config.csv_dir).join("dynamic")).unwrap())
-        .map(|path| path.unwrap().path())
-        .collect::<Vec<_>>();
-
-    // order vertex files before edge files
-    paths.sort_by_cached_key(|path| {
-        path.file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .split('_')
-            .count()
-    });
-
-    let mut context = Context::default();
-
-    for path in paths {
-        println!("import {:?}", path.as_os_str());
-        let label_name = resolve_file_name(&path);
-        match label_name {
-            LabelName::Vertex(label) => context.import_vertex(path, label).await,
-            LabelName::Edge(src_label, edge_label, dst_label) => {
-                context
-                    .import_edge(path, (src_label, edge_label, dst_label))
-                    .await
-            }
-        }
+    if let Err(err) = run(config).await {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
     }
-
-    // println!("{}", serde_json::to_string_pretty(&context.statistics).unwrap());
-    let file = std::fs::File::create(&config.output_file).unwrap();
-    let mut writer = std::io::BufWriter::new(file);
-    serde_json::to_writer_pretty(&mut writer, &context.statistics).unwrap();
-    writer.flush().unwrap();
-
-    // let file = std::fs::File::open(&config.output_file).unwrap();
-    // let mut reader = std::io::BufReader::new(file);
-    // let statistics: Statistics = serde_json::from_reader(&mut reader).unwrap();
-    // println!("{}", serde_json::to_string_pretty(&statistics).unwrap());
 }
@@ -0,0 +1,520 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    frequent::Frequent,
+    histogram::{equi_depth, Reservoir},
+    sketch::{hash_str, HyperLogLog},
+    statistics::{ColumnStatistics, CommonValue},
+};
+
+/// Value type of a property column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PropertyType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    #[serde(rename = "DATETIME")]
+    DateTime,
+}
+
+impl PropertyType {
+    /// Maps a Neo4j header type such as `LONG` or `DATETIME` to a property
+    /// type. Arrays and unknown types are treated as strings.
+    pub fn from_neo4j(name: &str) -> Self {
+        match name.to_ascii_uppercase().as_str() {
+            "INT" | "LONG" | "SHORT" | "BYTE" => PropertyType::Integer,
+            "FLOAT" | "DOUBLE" => PropertyType::Float,
+            "BOOLEAN" => PropertyType::Boolean,
+            "DATE" => PropertyType::Date,
+            "DATETIME" | "LOCALDATETIME" => PropertyType::DateTime,
+            _ => PropertyType::String,
+        }
+    }
+
+    /// Guesses the type of a column without a declared type from its first
+    /// non-empty value.
+    fn infer(value: &str) -> Self {
+        if value.parse::<i64>().is_ok() {
+            PropertyType::Integer
+        } else if value
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b".+-eE".contains(&b))
+            && value.parse::<f64>().is_ok()
+        {
+            PropertyType::Float
+        } else if value == "true" || value == "false" {
+            PropertyType::Boolean
+        } else if value.len() == 10 && parse_temporal(value).is_some() {
+            PropertyType::Date
+        } else if value.len() > 10 && parse_temporal(value).is_some() {
+            PropertyType::DateTime
+        } else {
+            PropertyType::String
+        }
+    }
+
+    /// Converts `value` to the number its min and max are tracked as, or `None`
+    /// if it is not a valid value of this type.
+    fn parse(self, value: &str) -> Option<Option<f64>> {
+        match self {
+            PropertyType::String => Some(None),
+            PropertyType::Integer => value.parse::<i64>().ok().map(|v| Some(v as f64)),
+            PropertyType::Float => value.parse::<f64>().ok().map(Some),
+            PropertyType::Boolean => match value {
+                "true" | "false" => Some(None),
+                _ => None,
+            },
+            PropertyType::Date | PropertyType::DateTime => {
+                parse_temporal(value).map(|millis| Some(millis as f64))
+            }
+        }
+    }
+}
+
+/// A property column of an input file.
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub index: usize,
+    pub name: String,
+    /// Declared type, or `None` if it is inferred from the values.
+    pub property_type: Option<PropertyType>,
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Year, month and day of a number of days since 1970-01-01, the inverse of
+/// `days_from_civil`.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Formats days since 1970-01-01 as the datagen writes dates, e.g.
+/// `1989-12-03`.
+pub fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats milliseconds since the Unix epoch as the datagen writes datetimes,
+/// e.g. `2010-05-01T00:00:00.000+00:00`.
+pub fn format_datetime(millis: i64) -> String {
+    let time = millis.rem_euclid(86_400_000);
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:03}+00:00",
+        format_date(millis.div_euclid(86_400_000)),
+        time / 3_600_000,
+        time / 60_000 % 60,
+        time / 1000 % 60,
+        time % 1000
+    )
+}
+
+fn number(s: &str) -> Option<i64> {
+    match !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        true => s.parse().ok(),
+        false => None,
+    }
+}
+
+/// Parses a date or datetime as written by the datagen into milliseconds since
+/// the Unix epoch, e.g. `1989-12-03`, `2010-05-01T00:00:00.000+0000` and
+/// `2010-05-01T00:00:00.000+00:00`. Plain integers are taken as milliseconds.
+pub fn parse_temporal(value: &str) -> Option<i64> {
+    if let Some(millis) = number(value) {
+        return Some(millis);
+    }
+
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (
+        number(parts.next()?)?,
+        number(parts.next()?)?,
+        number(parts.next()?)?,
+    );
+    if !(0..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+
+    if let Some(time) = time {
+        let (time, offset) = match time.find(['+', '-', 'Z']) {
+            Some(i) => time.split_at(i),
+            None => (time, ""),
+        };
+        let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let mut parts = time.splitn(3, ':');
+        let hours = number(parts.next()?)?;
+        let minutes = number(parts.next()?)?;
+        let seconds = parts.next().map_or(Some(0), number)?;
+        if hours > 23 || minutes > 59 || seconds > 60 {
+            return None;
+        }
+        let fraction_millis = match fraction.is_empty() {
+            true => 0,
+            false => {
+                number(fraction)?;
+                let digits = &fraction[..fraction.len().min(3)];
+                number(digits)? * 10i64.pow(3 - digits.len() as u32)
+            }
+        };
+        millis += ((hours * 60 + minutes) * 60 + seconds) * 1000 + fraction_millis;
+
+        let offset = offset.trim_start_matches('Z');
+        if !offset.is_empty() {
+            let (sign, digits) = match (offset.strip_prefix('+'), offset.strip_prefix('-')) {
+                (Some(digits), _) => (1, digits),
+                (_, Some(digits)) => (-1, digits),
+                _ => return None,
+            };
+            let digits = digits.replace(':', "");
+            number(&digits)?;
+            let (offset_hours, offset_minutes) = match digits.len() {
+                2 => (number(&digits)?, 0),
+                4 => (number(&digits[..2])?, number(&digits[2..])?),
+                _ => return None,
+            };
+            millis -= sign * (offset_hours * 60 + offset_minutes) * 60_000;
+        }
+    }
+
+    Some(millis)
+}
+
+/// How much detail `ColumnCounts::finish` keeps.
+#[derive(Debug, Clone, Copy)]
+pub struct Detail {
+    /// Number of histogram buckets of numeric and temporal columns.
+    pub histogram_buckets: usize,
+    /// Number of most common values of string columns.
+    pub most_common: usize,
+}
+
+/// Running statistics of a property column.
+#[derive(Debug, Clone)]
+pub struct ColumnCounts {
+    /// Declared or inferred type; `None` until the first value of a column
+    /// without a declared type.
+    property_type: Option<PropertyType>,
+    count: u64,
+    nulls: u64,
+    /// Total number of characters of the non-null values.
+    length: u64,
+    distinct: Distinct,
+    /// Smallest and largest value as numbers, while every value parses as one.
+    range: Option<(f64, f64)>,
+    /// Sample of the values as numbers for the histogram, kept under the same
+    /// condition. A sample of bounded size is kept in exact mode too, as
+    /// keeping every value of a large graph does not fit in memory.
+    values: Reservoir,
+    frequent: Frequent,
+}
+
+#[derive(Debug, Clone)]
+enum Distinct {
+    /// Hashes of the distinct values.
+    Exact(HashSet<u64>),
+    Approx(HyperLogLog),
+}
+
+impl ColumnCounts {
+    pub fn new(property_type: Option<PropertyType>, approx: bool) -> Self {
+        ColumnCounts {
+            property_type,
+            count: 0,
+            nulls: 0,
+            length: 0,
+            distinct: match approx {
+                true => Distinct::Approx(HyperLogLog::default()),
+                false => Distinct::Exact(HashSet::new()),
+            },
+            range: None,
+            values: Reservoir::default(),
+            frequent: Frequent::default(),
+        }
+    }
+
+    /// Adds a value, where an empty one is null.
+    pub fn add(&mut self, value: &str) {
+        self.count += 1;
+        if value.is_empty() {
+            self.nulls += 1;
+            return;
+        }
+        self.length += value.chars().count() as u64;
+        let hash = hash_str(value);
+        match &mut self.distinct {
+            Distinct::Exact(hashes) => {
+                hashes.insert(hash);
+            }
+            Distinct::Approx(sketch) => sketch.insert(hash),
+        }
+        self.frequent.insert(value);
+
+        let property_type = *self
+            .property_type
+            .get_or_insert_with(|| PropertyType::infer(value));
+        match property_type.parse(value) {
+            Some(Some(number)) => {
+                let (min, max) = self.range.get_or_insert((number, number));
+                *min = min.min(number);
+                *max = max.max(number);
+                self.values.insert(number);
+            }
+            Some(None) => (),
+            // values that do not match the type turn the column into a string column
+            None => self.downgrade(),
+        }
+    }
+
+    fn downgrade(&mut self) {
+        self.property_type = Some(PropertyType::String);
+        self.range = None;
+        self.values = Reservoir::default();
+    }
+
+    pub fn merge(&mut self, other: &ColumnCounts) {
+        self.count += other.count;
+        self.nulls += other.nulls;
+        self.length += other.length;
+        self.frequent.merge(&other.frequent);
+        match (&mut self.distinct, &other.distinct) {
+            (Distinct::Exact(hashes), Distinct::Exact(other_hashes)) => hashes.extend(other_hashes),
+            (Distinct::Approx(sketch), Distinct::Approx(other_sketch)) => {
+                sketch.merge(other_sketch)
+            }
+            _ => unreachable!("counts of one import are either all exact or all approximate"),
+        }
+
+        match (self.property_type, other.property_type) {
+            (_, None) => return,
+            (None, Some(_)) => self.property_type = other.property_type,
+            (Some(a), Some(b)) if a != b => return self.downgrade(),
+            _ => (),
+        }
+        self.range = match (self.range, other.range) {
+            (Some((min, max)), Some((other_min, other_max))) => {
+                Some((min.min(other_min), max.max(other_max)))
+            }
+            (range, None) | (None, range) => range,
+        };
+        self.values.merge(&other.values);
+    }
+
+    pub fn finish(self, detail: Detail) -> ColumnStatistics {
+        let property_type = self.property_type.unwrap_or(PropertyType::String);
+        let values = self.count - self.nulls;
+        let (distinct, sketch) = match self.distinct {
+            Distinct::Exact(hashes) => (hashes.len() as f64, None),
+            Distinct::Approx(sketch) => (sketch.estimate(), Some(sketch)),
+        };
+        ColumnStatistics {
+            property_type,
+            count: self.count,
+            null_fraction: match self.count {
+                0 => 0.0,
+                count => self.nulls as f64 / count as f64,
+            },
+            distinct,
+            min: self.range.map(|(min, _)| min),
+            max: self.range.map(|(_, max)| max),
+            avg_length: match values {
+                0 => 0.0,
+                values => self.length as f64 / values as f64,
+            },
+            histogram: match self.range {
+                Some((min, max)) if detail.histogram_buckets > 0 => {
+                    let mut bounds =
+                        equi_depth(self.values.into_sample(), detail.histogram_buckets);
+                    // the sample may have missed the extremes
+                    if let [first, .., last] = bounds.as_mut_slice() {
+                        *first = min;
+                        *last = max;
+                    }
+                    Some(bounds)
+                }
+                _ => None,
+            },
+            most_common: match property_type {
+                PropertyType::String if detail.most_common > 0 => {
+                    let values = self.frequent.top(detail.most_common);
+                    // values occurring once are not more common than any other
+                    match values.first() {
+                        Some((_, count)) if *count > 1 => Some(
+                            values
+                                .into_iter()
+                                .map(|(value, count)| CommonValue { value, count })
+                                .collect(),
+                        ),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            sketch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_datagen_dates_and_datetimes() {
+        assert_eq!(parse_temporal("1970-01-01"), Some(0));
+        assert_eq!(parse_temporal("1989-12-03"), Some(628_646_400_000));
+        let millis = 1_272_672_000_000;
+        assert_eq!(parse_temporal("2010-05-01T00:00:00.000+0000"), Some(millis));
+        assert_eq!(
+            parse_temporal("2010-05-01T00:00:00.000+00:00"),
+            Some(millis)
+        );
+        assert_eq!(parse_temporal("2010-05-01T00:00:00Z"), Some(millis));
+        assert_eq!(parse_temporal("2010-05-01 02:00+02:00"), Some(millis));
+        assert_eq!(
+            parse_temporal("2010-04-30T22:00:00.5-02"),
+            Some(millis + 500)
+        );
+        assert_eq!(parse_temporal("1272672000000"), Some(millis));
+    }
+
+    #[test]
+    fn rejects_malformed_temporals() {
+        for value in [
+            "",
+            "2010-05",
+            "2010-13-01",
+            "2010-05-32",
+            "2010-05-01T25:00",
+            "2010-05-01T10:00+1",
+            "2010-05-01T10:00:00.x",
+            "2010-05-01 10:00Zé",
+            "2010-05-01 10:00+aé",
+            "2010-05-01 10:00+0é",
+            "99999999999999999-01-01",
+        ] {
+            assert_eq!(parse_temporal(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn infers_types_from_first_value() {
+        assert_eq!(PropertyType::infer("42"), PropertyType::Integer);
+        assert_eq!(PropertyType::infer("-1.5e3"), PropertyType::Float);
+        assert_eq!(PropertyType::infer("true"), PropertyType::Boolean);
+        assert_eq!(PropertyType::infer("1989-12-03"), PropertyType::Date);
+        assert_eq!(
+            PropertyType::infer("2010-05-01T00:00:00.000+00:00"),
+            PropertyType::DateTime
+        );
+        assert_eq!(PropertyType::infer("NaN"), PropertyType::String);
+        assert_eq!(
+            PropertyType::infer("2010-01-01 10:00Zé"),
+            PropertyType::String
+        );
+        assert_eq!(PropertyType::infer("Firefox"), PropertyType::String);
+    }
+
+    #[test]
+    fn formatted_temporals_parse_back() {
+        assert_eq!(format_date(7_276), "1989-12-03");
+        assert_eq!(
+            format_datetime(1_272_672_000_123),
+            "2010-05-01T00:00:00.123+00:00"
+        );
+        assert_eq!(format_datetime(-1), "1969-12-31T23:59:59.999+00:00");
+        for millis in [0, -1, 1_272_672_000_123, 1_354_060_800_000] {
+            assert_eq!(parse_temporal(&format_datetime(millis)), Some(millis));
+        }
+        assert_eq!(PropertyType::infer(&format_date(7_276)), PropertyType::Date);
+    }
+
+    #[test]
+    fn builds_histograms_of_large_columns_from_a_sample() {
+        let mut column = ColumnCounts::new(Some(PropertyType::Integer), false);
+        for value in 0..200_000 {
+            column.add(&value.to_string());
+        }
+        let detail = Detail {
+            histogram_buckets: 4,
+            most_common: 0,
+        };
+        let statistics = column.finish(detail);
+        assert_eq!(statistics.distinct, 200_000.0);
+        let histogram = statistics.histogram.unwrap();
+        assert_eq!(histogram.len(), 5);
+        assert_eq!((histogram[0], histogram[4]), (0.0, 199_999.0));
+        for (i, &bound) in histogram.iter().enumerate().take(4).skip(1) {
+            assert!(
+                (bound - 50_000.0 * i as f64).abs() < 2_000.0,
+                "{:?}",
+                histogram
+            );
+        }
+    }
+
+    #[test]
+    fn lists_most_common_values_occurring_more_than_once() {
+        let detail = Detail {
+            histogram_buckets: 0,
+            most_common: 2,
+        };
+        let mut column = ColumnCounts::new(Some(PropertyType::String), false);
+        for value in ["Chrome", "Firefox", "Chrome", "Safari", "Firefox", "Chrome"] {
+            column.add(value);
+        }
+        let most_common = column.finish(detail).most_common.unwrap();
+        let values = most_common
+            .iter()
+            .map(|value| (value.value.as_str(), value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [("Chrome", 3), ("Firefox", 2)]);
+
+        let mut column = ColumnCounts::new(Some(PropertyType::String), false);
+        for value in ["http://a", "http://b", "http://c"] {
+            column.add(value);
+        }
+        assert_eq!(column.finish(detail).most_common, None);
+    }
+
+    #[test]
+    fn civil_from_days_inverts_days_from_civil() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month as i64, day as i64), days);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    input::InputFile,
+};
+
+const DEFAULT_SCHEMA: &str = include_str!("default_schema.toml");
+
+/// Maps input files to the vertex or edge labels they contain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schema {
+    #[serde(default)]
+    pub vertices: Vec<VertexMapping>,
+    #[serde(default)]
+    pub edges: Vec<EdgeMapping>,
+    /// Patterns of files that are not imported, e.g. multi-valued properties.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VertexMapping {
+    pub file: String,
+    pub label: String,
+    /// Column holding the concrete label in headers without a `:LABEL` column.
+    #[serde(default)]
+    pub label_column: Option<String>,
+    /// Columns referencing other vertices, each of which yields an edge.
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+/// A column of a vertex file holding the id of an adjacent vertex, as written by
+/// the `CsvMergeForeign` serializers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKey {
+    pub column: String,
+    pub label: String,
+    pub dst: String,
+    /// The edge points from the referenced vertex to the referencing one.
+    #[serde(default)]
+    pub reverse: bool,
+}
+
+impl ForeignKey {
+    /// Orders the referencing and the referenced vertex as source and
+    /// destination of the edge.
+    pub fn endpoints<T>(&self, own: T, referenced: T) -> (T, T) {
+        if self.reverse {
+            (referenced, own)
+        } else {
+            (own, referenced)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeMapping {
+    pub file: String,
+    pub src: String,
+    pub label: String,
+    pub dst: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Entity {
+    Vertex(VertexMapping),
+    Edge(EdgeMapping),
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        toml::from_str(DEFAULT_SCHEMA).expect("built-in schema is valid")
+    }
+}
+
+impl Schema {
+    /// Loads a schema from a TOML, JSON or YAML file, chosen by extension.
+    pub fn load(path: &Path) -> Result<Self> {
+        let schema_error = |source: Box<dyn std::error::Error + Send + Sync>| Error::Schema {
+            path: path.to_owned(),
+            source,
+        };
+        let text = std::fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("toml") => toml::from_str(&text).map_err(|err| schema_error(err.into())),
+            Some("json") => serde_json::from_str(&text).map_err(|err| schema_error(err.into())),
+            Some("yaml" | "yml") => {
+                serde_yaml::from_str(&text).map_err(|err| schema_error(err.into()))
+            }
+            _ => Err(schema_error(
+                "unknown schema format, expected .toml, .json, .yaml or .yml".into(),
+            )),
+        }
+    }
+
+    /// Resolves the mapping of `file` from its entity name, or `None` if the
+    /// file is ignored.
+    ///
+    /// If several patterns match, the one with the most literal characters wins,
+    /// and among those the first declared one.
+    pub fn resolve(&self, file: &InputFile) -> Result<Option<Entity>> {
+        let name = &file.entity;
+
+        let vertices = self
+            .vertices
+            .iter()
+            .map(|v| (&v.file, Some(Entity::Vertex(v.clone()))));
+        let edges = self
+            .edges
+            .iter()
+            .map(|e| (&e.file, Some(Entity::Edge(e.clone()))));
+        let ignored = self.ignore.iter().map(|pattern| (pattern, None));
+        vertices
+            .chain(edges)
+            .chain(ignored)
+            .filter(|(pattern, _)| matches(pattern, name))
+            .rev()
+            .max_by_key(|(pattern, _)| pattern.chars().filter(|&c| c != '*').count())
+            .map(|(_, entity)| entity)
+            .ok_or_else(|| Error::FileName {
+                path: file.path.clone(),
+            })
+    }
+}
+
+/// Case-insensitive glob match where `*` matches any sequence of characters.
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(entity: &str) -> InputFile {
+        InputFile {
+            entity: entity.to_owned(),
+            path: format!("{}_0_0.csv", entity).into(),
+        }
+    }
+
+    fn label(schema: &Schema, entity: &str) -> Option<String> {
+        match schema.resolve(&file(entity)).unwrap() {
+            Some(Entity::Vertex(mapping)) => Some(mapping.label),
+            Some(Entity::Edge(mapping)) => Some(mapping.label),
+            None => None,
+        }
+    }
+
+    fn vertex(file: &str, label: &str) -> VertexMapping {
+        VertexMapping {
+            file: file.to_owned(),
+            label: label.to_owned(),
+            label_column: None,
+            foreign_keys: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(matches("person", "person"));
+        assert!(!matches("person", "person_knows_person"));
+        assert!(matches("*_knows_person", "person_knows_person"));
+        assert!(matches("person_*", "person_knows_person"));
+        assert!(matches("person_*_person", "person_knows_person"));
+        assert!(matches("*", ""));
+        assert!(!matches("person_*_person", "person_person"));
+        // the parts of a pattern may not overlap in the name
+        assert!(!matches("*ab*ba", "aba"));
+        assert!(matches("*ab*ba", "abba"));
+        assert!(!matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn matches_case_insensitively() {
+        assert!(matches("Person_KNOWS_*", "person_knows_person"));
+        let schema = Schema::default();
+        assert_eq!(label(&schema, "Person"), Some("Person".to_owned()));
+        assert_eq!(
+            label(&schema, "Person_knows_Person"),
+            Some("KNOWS".to_owned())
+        );
+    }
+
+    #[test]
+    fn resolves_the_most_literal_pattern() {
+        let schema = Schema {
+            vertices: vec![
+                vertex("*", "Any"),
+                vertex("person*", "Person"),
+                vertex("*_person", "Suffix"),
+                vertex("person_*", "PersonPart"),
+            ],
+            edges: Vec::new(),
+            ignore: vec!["person_email_*".to_owned()],
+        };
+        assert_eq!(label(&schema, "forum"), Some("Any".to_owned()));
+        assert_eq!(label(&schema, "person"), Some("Person".to_owned()));
+        // `person_*` and `*_person` have as many literal characters, and the
+        // first declared wins
+        assert_eq!(
+            label(&schema, "person_knows_person"),
+            Some("Suffix".to_owned())
+        );
+        // an ignore pattern with more literal characters beats vertex patterns
+        assert_eq!(label(&schema, "person_email_emailaddress"), None);
+        assert_eq!(label(&Schema::default(), "person_email_emailaddress"), None);
+    }
+
+    #[test]
+    fn rejects_unmapped_files() {
+        let schema = Schema {
+            vertices: vec![vertex("person", "Person")],
+            edges: Vec::new(),
+            ignore: Vec::new(),
+        };
+        assert!(matches!(
+            schema.resolve(&file("forum")),
+            Err(Error::FileName { .. })
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Number of index bits of a `HyperLogLog`, giving a standard error of
+/// about 1.6%.
+const PRECISION: u32 = 12;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// A HyperLogLog sketch counting distinct hashes in constant memory.
+///
+/// Sketches are serialised as the base64 encoding of their registers, so that
+/// the sketches of separately counted shards can be merged afterwards.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Box<[u8]>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog {
+            registers: vec![0; REGISTERS].into_boxed_slice(),
+        }
+    }
+}
+
+impl fmt::Debug for HyperLogLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HyperLogLog")
+            .field("estimate", &self.estimate())
+            .finish()
+    }
+}
+
+impl HyperLogLog {
+    /// Adds a value given by its hash, see `hash_str` and `hash_vertex`.
+    pub fn insert(&mut self, hash: u64) {
+        let index = (hash >> (64 - PRECISION)) as usize;
+        let rank = ((hash << PRECISION).leading_zeros() + 1).min(64 - PRECISION + 1) as u8;
+        let register = &mut self.registers[index];
+        *register = (*register).max(rank);
+    }
+
+    /// Adds the values of `other`, as if they had been inserted into `self`.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, &rank) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(rank);
+        }
+    }
+
+    /// Estimates the number of distinct values inserted.
+    pub fn estimate(&self) -> f64 {
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2f64.powi(-(rank as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        // linear counting is more accurate for small cardinalities
+        let zeros = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+}
+
+impl Serialize for HyperLogLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(&self.registers))
+    }
+}
+
+impl<'de> Deserialize<'de> for HyperLogLog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let registers = STANDARD.decode(encoded).map_err(de::Error::custom)?;
+        if registers.len() != REGISTERS {
+            return Err(de::Error::invalid_length(
+                registers.len(),
+                &"4096 HyperLogLog registers",
+            ));
+        }
+        Ok(HyperLogLog {
+            registers: registers.into_boxed_slice(),
+        })
+    }
+}
+
+/// Finaliser of SplitMix64, spreading the bits of `x` over the whole word.
+pub(crate) fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Hashes a string with FNV-1a. Unlike `std`'s hashers the result is stable
+/// across builds, which sketches merged from separate runs rely on.
+pub fn hash_str(s: &str) -> u64 {
+    let hash = s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    mix(hash)
+}
+
+/// Hashes a vertex given by its concrete label and id, since ids are only
+/// unique within a label.
+pub fn hash_vertex(label: &str, id: u64) -> u64 {
+    mix(hash_str(label) ^ mix(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sketch(values: std::ops::Range<u64>) -> HyperLogLog {
+        let mut sketch = HyperLogLog::default();
+        for value in values {
+            sketch.insert(hash_vertex("Person", value));
+        }
+        sketch
+    }
+
+    #[test]
+    fn estimates_distinct_values() {
+        assert_eq!(HyperLogLog::default().estimate(), 0.0);
+        for distinct in [10, 1_000, 100_000, 1_000_000] {
+            let estimate = sketch(0..distinct).estimate();
+            let error = (estimate - distinct as f64).abs() / distinct as f64;
+            assert!(error < 0.05, "{} estimated as {}", distinct, estimate);
+        }
+        // duplicates are not counted again
+        let mut duplicates = sketch(0..1_000);
+        duplicates.merge(&sketch(0..1_000));
+        assert_eq!(duplicates, sketch(0..1_000));
+    }
+
+    #[test]
+    fn merges_like_inserting_both() {
+        let mut merged = sketch(0..60_000);
+        merged.merge(&sketch(40_000..100_000));
+        assert_eq!(merged, sketch(0..100_000));
+    }
+
+    #[test]
+    fn round_trips_through_serde() {
+        let sketch = sketch(0..10_000);
+        let json = serde_json::to_string(&sketch).unwrap();
+        let decoded: HyperLogLog = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, sketch);
+        assert_eq!(decoded.estimate(), sketch.estimate());
+    }
+
+    #[test]
+    fn rejects_registers_of_other_lengths() {
+        let json = format!("{:?}", STANDARD.encode([0u8; 3]));
+        let err = serde_json::from_str::<HyperLogLog>(&json).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid length 3, expected 4096 HyperLogLog registers"),
+            "{}",
+            err
+        );
+        assert!(serde_json::from_str::<HyperLogLog>("\"not base64!\"").is_err());
+    }
+}
@@ -0,0 +1,1055 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result, SkipReason},
+    hierarchy::LabelHierarchy,
+    histogram::merge_histograms,
+    property::PropertyType,
+    sketch::HyperLogLog,
+};
+
+pub type VertexCardinality = HashMap<String, f64>;
+pub type EdgeCardinality = HashMap<String, HashMap<String, HashMap<String, f64>>>;
+/// Degree distributions keyed like `EdgeCardinality`.
+pub type DegreeDistributions =
+    HashMap<String, HashMap<String, HashMap<String, DegreeDistribution>>>;
+/// Distinct endpoint counts keyed like `EdgeCardinality`.
+pub type DistinctCounts = HashMap<String, HashMap<String, HashMap<String, DistinctEndpoints>>>;
+/// HyperLogLog sketches of the endpoints, keyed like `EdgeCardinality`.
+pub type Sketches = HashMap<String, HashMap<String, HashMap<String, EndpointSketches>>>;
+/// Number of paths of two edges, keyed by their pattern, e.g.
+/// `(Comment)-[REPLY_OF]->(Post)-[HAS_CREATOR]->(Person)`.
+pub type Path2Cardinality = HashMap<String, f64>;
+/// Cycle statistics of homogeneous triples, keyed like `EdgeCardinality`.
+pub type CycleCounts = HashMap<String, HashMap<String, HashMap<String, CycleStatistics>>>;
+/// Number of vertices or edges per bucket of their `creationDate`, e.g.
+/// `2010-05` when bucketed by month.
+pub type TimeSeries = BTreeMap<String, f64>;
+/// Time series of the vertices of each label.
+pub type VertexSeries = HashMap<String, TimeSeries>;
+/// Time series of the edges of each triple, keyed like `EdgeCardinality`.
+pub type EdgeSeries = HashMap<String, HashMap<String, HashMap<String, TimeSeries>>>;
+/// Characteristic sets of the vertices of each label, most common first.
+pub type CharacteristicSets = HashMap<String, Vec<CharacteristicSet>>;
+/// Statistics of the properties of each label, keyed by label and property name.
+pub type PropertyMap = HashMap<String, HashMap<String, ColumnStatistics>>;
+/// Statistics of the properties of each edge triple, keyed like
+/// `EdgeCardinality` and then by property name.
+pub type EdgePropertyMap = HashMap<String, HashMap<String, PropertyMap>>;
+/// Number of skipped rows per file and reason. Rows whose foreign key is unknown
+/// are counted as vertices, and only their foreign-key edge is tallied here.
+pub type SkippedRows = BTreeMap<String, BTreeMap<SkipReason, u64>>;
+
+/// Vertex and edge cardinalities of a dataset, keyed by label.
+///
+/// Counts are recorded under the concrete labels as well as their ancestors,
+/// e.g. `City` and `Place`, and the empty label `""` matches any label.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {
+    pub vertex_cardinality: VertexCardinality,
+    pub edge_cardinality: EdgeCardinality,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub degree_distribution: DegreeDistributions,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub distinct_endpoints: DistinctCounts,
+    /// Sketches behind `distinct_endpoints` when counted approximately.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sketches: Sketches,
+    /// Counted only on request. Paths never use the same edge twice, vertex
+    /// labels are rolled up to their ancestors but not to `""`, and edge
+    /// labels are concrete.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub path2_cardinality: Path2Cardinality,
+    /// Counted only for the triples asked for, under their concrete labels.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub cycles: CycleCounts,
+    /// Counted only on request, for vertices and edges with a valid
+    /// `creationDate`. Foreign-key edges are bucketed by the `creationDate` of
+    /// their vertex.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub vertex_series: VertexSeries,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub edge_series: EdgeSeries,
+    /// Left out in approximate mode. Vertex labels are rolled up to their
+    /// ancestors but not to `""`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub characteristic_sets: CharacteristicSets,
+    /// Counted only on request.
+    #[serde(default, skip_serializing_if = "PropertyStatistics::is_empty")]
+    pub property_statistics: PropertyStatistics,
+    /// Parent of each concrete label read from a label column, e.g. `Place`
+    /// for `City`, with which updates are rolled up like the initial import.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub parent_labels: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub skipped_rows: SkippedRows,
+}
+
+/// Triangles and 4-cycles among the vertices of a homogeneous triple such as
+/// `(Person, KNOWS, Person)`, whose edges are taken as undirected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CycleStatistics {
+    /// Number of vertices with at least one edge.
+    pub vertices: u64,
+    /// Number of connected vertex pairs.
+    pub edges: u64,
+    /// Number of paths of two edges, with either end first.
+    pub wedges: u64,
+    pub triangles: u64,
+    pub four_cycles: u64,
+    /// Fraction of the wedges that are closed by a third edge.
+    pub clustering_coefficient: f64,
+    /// Average over the vertices of the fraction of their neighbour pairs that
+    /// are connected, where vertices with fewer than two neighbours count as 0.
+    pub average_clustering: f64,
+}
+
+impl CycleStatistics {
+    /// Adds the statistics of the same triple of another shard.
+    pub fn merge(&mut self, other: &CycleStatistics) {
+        let vertices = self.vertices + other.vertices;
+        self.average_clustering = match vertices {
+            0 => 0.0,
+            vertices => {
+                (self.average_clustering * self.vertices as f64
+                    + other.average_clustering * other.vertices as f64)
+                    / vertices as f64
+            }
+        };
+        self.vertices = vertices;
+        self.edges += other.edges;
+        self.wedges += other.wedges;
+        self.triangles += other.triangles;
+        self.four_cycles += other.four_cycles;
+        self.clustering_coefficient = match self.wedges {
+            0 => 0.0,
+            wedges => 3.0 * self.triangles as f64 / wedges as f64,
+        };
+    }
+}
+
+/// The vertices whose outgoing edges have exactly the same set of labels.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacteristicSet {
+    /// Number of vertices.
+    pub count: u64,
+    /// Average number of outgoing edges of each label of the set per vertex.
+    pub multiplicities: BTreeMap<String, f64>,
+}
+
+impl CharacteristicSet {
+    fn same_labels(&self, other: &CharacteristicSet) -> bool {
+        self.multiplicities.keys().eq(other.multiplicities.keys())
+    }
+
+    /// Adds the vertices of a set with the same labels.
+    fn merge(&mut self, other: &CharacteristicSet) {
+        let count = self.count + other.count;
+        if count > 0 {
+            for (label, multiplicity) in &mut self.multiplicities {
+                let other_multiplicity = other.multiplicities.get(label).copied().unwrap_or(0.0);
+                *multiplicity = (*multiplicity * self.count as f64
+                    + other_multiplicity * other.count as f64)
+                    / count as f64;
+            }
+        }
+        self.count = count;
+    }
+}
+
+/// Adds `other` to `sets`, merging sets with the same labels and keeping the
+/// most common first.
+pub(crate) fn merge_characteristic_sets(
+    sets: &mut Vec<CharacteristicSet>,
+    other: &[CharacteristicSet],
+) {
+    for set in other {
+        match sets.iter_mut().find(|s| s.same_labels(set)) {
+            Some(entry) => entry.merge(set),
+            None => sets.push(set.clone()),
+        }
+    }
+    sets.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.multiplicities.keys().cmp(b.multiplicities.keys()))
+    });
+}
+
+fn add_series(series: &mut TimeSeries, other: TimeSeries) {
+    for (bucket, count) in other {
+        *series.entry(bucket).or_insert(0.0) += count;
+    }
+}
+
+fn merge_property_map(map: &mut PropertyMap, other: PropertyMap) {
+    for (label, properties) in other {
+        let label_entry = map.entry(label).or_default();
+        for (name, column) in properties {
+            match label_entry.get_mut(&name) {
+                Some(entry) => entry.merge(&column),
+                None => {
+                    label_entry.insert(name, column);
+                }
+            }
+        }
+    }
+}
+
+/// Edges of the triples of concrete labels below a triple, before and after
+/// `Statistics::remove`, with the distinct endpoints weighting their fractions
+/// kept.
+#[derive(Debug, Default)]
+struct Removal {
+    edges: f64,
+    /// Edges deleted explicitly.
+    explicit: f64,
+    kept_edges: f64,
+    sources: f64,
+    kept_sources: f64,
+    destinations: f64,
+    kept_destinations: f64,
+}
+
+fn triple_entry<'a, V: Default>(
+    map: &'a mut HashMap<String, HashMap<String, HashMap<String, V>>>,
+    [src_label, edge_label, dst_label]: [&str; 3],
+) -> &'a mut V {
+    map.entry(src_label.to_owned())
+        .or_default()
+        .entry(edge_label.to_owned())
+        .or_default()
+        .entry(dst_label.to_owned())
+        .or_default()
+}
+
+fn get_triple<'a, V>(
+    map: &'a HashMap<String, HashMap<String, HashMap<String, V>>>,
+    [src_label, edge_label, dst_label]: [&str; 3],
+) -> Option<&'a V> {
+    map.get(src_label)?.get(edge_label)?.get(dst_label)
+}
+
+/// Number of distinct vertices with at least one outgoing or incoming edge of an
+/// edge triple.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DistinctEndpoints {
+    pub sources: f64,
+    pub destinations: f64,
+}
+
+/// Statistics of the property columns of vertex and edge files. Vertex labels,
+/// and the endpoint labels of edge triples, are rolled up to their ancestors.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyStatistics {
+    #[serde(default)]
+    pub vertices: PropertyMap,
+    #[serde(default)]
+    pub edges: EdgePropertyMap,
+}
+
+impl PropertyStatistics {
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty() && self.edges.is_empty()
+    }
+}
+
+/// Statistics of a single property column.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnStatistics {
+    #[serde(rename = "type")]
+    pub property_type: PropertyType,
+    /// Number of rows, including those where the property is null.
+    pub count: u64,
+    /// Fraction of rows with an empty value.
+    pub null_fraction: f64,
+    /// Number of distinct non-null values.
+    pub distinct: f64,
+    /// Smallest and largest value of numeric and temporal properties. Dates
+    /// and datetimes are given in milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Average number of characters of the non-null values.
+    pub avg_length: f64,
+    /// Bucket bounds of an equi-depth histogram of numeric and temporal
+    /// properties, from `min` to `max`: each of the `histogram.len() - 1`
+    /// buckets holds about the same number of non-null values. Built from a
+    /// sample of the values of columns with more than 65536 of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<Vec<f64>>,
+    /// Most common values of string properties, most frequent first. Counts
+    /// are exact for properties with at most 256 distinct values. Otherwise
+    /// they may be low by up to 1/257th of the rows, and only values known to
+    /// be more common than all unlisted ones are listed. Left out if no value
+    /// occurs more than once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub most_common: Option<Vec<CommonValue>>,
+    /// Sketch behind `distinct` when counted approximately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sketch: Option<HyperLogLog>,
+}
+
+impl ColumnStatistics {
+    /// Adds the statistics of the same property of another shard.
+    pub fn merge(&mut self, other: &ColumnStatistics) {
+        let nulls =
+            self.null_fraction * self.count as f64 + other.null_fraction * other.count as f64;
+        let values = (self.count as f64 * (1.0 - self.null_fraction))
+            + (other.count as f64 * (1.0 - other.null_fraction));
+        let length = self.avg_length * self.count as f64 * (1.0 - self.null_fraction)
+            + other.avg_length * other.count as f64 * (1.0 - other.null_fraction);
+        let histogram_weights = (
+            (self.count as f64 * (1.0 - self.null_fraction)).round() as u64,
+            (other.count as f64 * (1.0 - other.null_fraction)).round() as u64,
+        );
+        self.count += other.count;
+        self.null_fraction = match self.count {
+            0 => 0.0,
+            count => nulls / count as f64,
+        };
+        self.avg_length = match values > 0.0 {
+            true => length / values,
+            false => 0.0,
+        };
+
+        // summed, which overcounts values occurring in several shards unless
+        // they were sketched
+        match (&mut self.sketch, &other.sketch) {
+            (Some(sketch), Some(other_sketch)) => {
+                sketch.merge(other_sketch);
+                self.distinct = sketch.estimate();
+            }
+            _ => self.distinct += other.distinct,
+        }
+
+        if self.property_type != other.property_type {
+            self.property_type = PropertyType::String;
+            self.min = None;
+            self.max = None;
+            self.histogram = None;
+            self.most_common = None;
+            return;
+        }
+        self.min = match (self.min, other.min) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        self.histogram = match (&self.histogram, &other.histogram) {
+            (Some(a), Some(b)) => Some(merge_histograms(
+                a,
+                histogram_weights.0,
+                b,
+                histogram_weights.1,
+            )),
+            (a, b) => a.clone().or_else(|| b.clone()),
+        };
+
+        // values only listed by one shard may be missing from the other's list,
+        // which undercounts them
+        match (&mut self.most_common, &other.most_common) {
+            (Some(values), Some(other_values)) => {
+                let k = values.len().max(other_values.len());
+                for other_value in other_values {
+                    match values.iter_mut().find(|v| v.value == other_value.value) {
+                        Some(value) => value.count += other_value.count,
+                        None => values.push(other_value.clone()),
+                    }
+                }
+                values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+                values.truncate(k);
+            }
+            (most_common @ None, other_values) => most_common.clone_from(other_values),
+            (Some(_), None) => (),
+        }
+    }
+}
+
+/// A value of a property and the number of rows it occurs in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommonValue {
+    pub value: String,
+    pub count: u64,
+}
+
+/// Sketches of the source and destination vertices of an edge triple.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointSketches {
+    pub sources: HyperLogLog,
+    pub destinations: HyperLogLog,
+}
+
+impl EndpointSketches {
+    pub fn merge(&mut self, other: &EndpointSketches) {
+        self.sources.merge(&other.sources);
+        self.destinations.merge(&other.destinations);
+    }
+
+    pub fn distinct(&self) -> DistinctEndpoints {
+        DistinctEndpoints {
+            sources: self.sources.estimate(),
+            destinations: self.destinations.estimate(),
+        }
+    }
+}
+
+/// Out-degrees of the source vertices and in-degrees of the destination vertices
+/// of an edge triple.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DegreeDistribution {
+    pub out_degree: Distribution,
+    pub in_degree: Distribution,
+}
+
+/// Distribution of the degrees of the vertices with at least one edge.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    /// Number of vertices with at least one edge.
+    pub vertices: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    /// `histogram[i]` is the number of vertices with a degree in
+    /// `[2^i, 2^(i + 1))`.
+    pub histogram: Vec<u64>,
+}
+
+impl Distribution {
+    /// Summarises the degrees of individual vertices, none of which may be 0.
+    pub fn from_degrees(mut degrees: Vec<u64>) -> Self {
+        if degrees.is_empty() {
+            return Distribution::default();
+        }
+        degrees.sort_unstable();
+
+        // nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p * degrees.len() as f64).ceil() as usize;
+            degrees[rank.clamp(1, degrees.len()) - 1]
+        };
+
+        let mut histogram = Vec::new();
+        for &degree in &degrees {
+            let bucket = degree.max(1).ilog2() as usize;
+            if histogram.len() <= bucket {
+                histogram.resize(bucket + 1, 0);
+            }
+            histogram[bucket] += 1;
+        }
+
+        Distribution {
+            vertices: degrees.len() as u64,
+            max: degrees[degrees.len() - 1],
+            mean: degrees.iter().sum::<u64>() as f64 / degrees.len() as f64,
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            p99: percentile(0.99),
+            histogram,
+        }
+    }
+
+    /// Adds the distribution of another shard, assuming it covers different
+    /// vertices. Percentiles cannot be combined exactly, so the larger of the
+    /// two is kept.
+    pub fn merge(&mut self, other: &Distribution) {
+        let vertices = self.vertices + other.vertices;
+        if vertices > 0 {
+            self.mean = (self.mean * self.vertices as f64 + other.mean * other.vertices as f64)
+                / vertices as f64;
+        }
+        self.vertices = vertices;
+        self.max = self.max.max(other.max);
+        self.p50 = self.p50.max(other.p50);
+        self.p90 = self.p90.max(other.p90);
+        self.p99 = self.p99.max(other.p99);
+        if self.histogram.len() < other.histogram.len() {
+            self.histogram.resize(other.histogram.len(), 0);
+        }
+        for (bucket, count) in self.histogram.iter_mut().zip(&other.histogram) {
+            *bucket += count;
+        }
+    }
+}
+
+impl Statistics {
+    /// Reads statistics from a JSON file written by `save`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        serde_json::from_reader(BufReader::new(file)).map_err(|source| Error::Json {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Writes the statistics to `path` as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let io_error = |source| Error::Io {
+            path: path.to_owned(),
+            source,
+        };
+        let mut writer = BufWriter::new(File::create(path).map_err(io_error)?);
+        serde_json::to_writer_pretty(&mut writer, self).map_err(|source| Error::Json {
+            path: path.to_owned(),
+            source,
+        })?;
+        writer.flush().map_err(io_error)
+    }
+
+    /// Adds the counts of `other`, e.g. of another shard of the same dataset.
+    pub fn merge(&mut self, other: Statistics) {
+        for (label, count) in other.vertex_cardinality {
+            *self.vertex_cardinality.entry(label).or_insert(0.0) += count;
+        }
+        for (src_label, edges) in other.edge_cardinality {
+            let src_entry = self.edge_cardinality.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, count) in dsts {
+                    *edge_entry.entry(dst_label).or_insert(0.0) += count;
+                }
+            }
+        }
+        for (src_label, edges) in other.degree_distribution {
+            let src_entry = self.degree_distribution.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, distribution) in dsts {
+                    let entry = edge_entry.entry(dst_label).or_default();
+                    entry.out_degree.merge(&distribution.out_degree);
+                    entry.in_degree.merge(&distribution.in_degree);
+                }
+            }
+        }
+        // summed, which overcounts vertices with edges in several shards unless
+        // they were sketched
+        for (src_label, edges) in other.distinct_endpoints {
+            let src_entry = self.distinct_endpoints.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, distinct) in dsts {
+                    let entry = edge_entry.entry(dst_label).or_default();
+                    entry.sources += distinct.sources;
+                    entry.destinations += distinct.destinations;
+                }
+            }
+        }
+        for (src_label, edges) in other.sketches {
+            let src_entry = self.sketches.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, sketches) in dsts {
+                    edge_entry.entry(dst_label).or_default().merge(&sketches);
+                }
+            }
+        }
+        for (src_label, edges) in &self.sketches {
+            for (edge_label, dsts) in edges {
+                for (dst_label, sketches) in dsts {
+                    let distinct = self
+                        .distinct_endpoints
+                        .entry(src_label.clone())
+                        .or_default()
+                        .entry(edge_label.clone())
+                        .or_default();
+                    distinct.insert(dst_label.clone(), sketches.distinct());
+                }
+            }
+        }
+        for (label, series) in other.vertex_series {
+            add_series(self.vertex_series.entry(label).or_default(), series);
+        }
+        for (src_label, edges) in other.edge_series {
+            let src_entry = self.edge_series.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, series) in dsts {
+                    add_series(edge_entry.entry(dst_label).or_default(), series);
+                }
+            }
+        }
+        for (pattern, count) in other.path2_cardinality {
+            *self.path2_cardinality.entry(pattern).or_insert(0.0) += count;
+        }
+        // vertices with edges in several shards are counted in each of them
+        for (label, sets) in other.characteristic_sets {
+            merge_characteristic_sets(self.characteristic_sets.entry(label).or_default(), &sets);
+        }
+        // summed, which misses cycles spanning several shards
+        for (src_label, edges) in other.cycles {
+            let src_entry = self.cycles.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label).or_default();
+                for (dst_label, cycles) in dsts {
+                    edge_entry.entry(dst_label).or_default().merge(&cycles);
+                }
+            }
+        }
+        merge_property_map(
+            &mut self.property_statistics.vertices,
+            other.property_statistics.vertices,
+        );
+        for (src_label, edges) in other.property_statistics.edges {
+            let src_entry = self.property_statistics.edges.entry(src_label).or_default();
+            for (edge_label, dsts) in edges {
+                merge_property_map(src_entry.entry(edge_label).or_default(), dsts);
+            }
+        }
+        self.parent_labels.extend(other.parent_labels);
+        self.add_skipped_rows(other.skipped_rows);
+    }
+
+    /// Adds the vertices and edges counted in `inserted`, the statistics of an
+    /// insert batch imported with the same `parent_labels`.
+    ///
+    /// Unlike `merge`, the inserted edges may connect vertices that already
+    /// have edges. Cardinalities are summed, and distinct endpoints of the
+    /// triples with inserted edges grow with their sketches in approximate
+    /// mode, and are dropped otherwise unless the triple is new. The sections
+    /// that cannot be updated without the vertices behind them, see
+    /// `derived_sections`, are dropped.
+    pub fn apply_inserts(&mut self, inserted: Statistics) {
+        for (label, count) in inserted.vertex_cardinality {
+            *self.vertex_cardinality.entry(label).or_insert(0.0) += count;
+        }
+        for (src_label, edges) in inserted.edge_cardinality {
+            let src_entry = self.edge_cardinality.entry(src_label.clone()).or_default();
+            for (edge_label, dsts) in edges {
+                let edge_entry = src_entry.entry(edge_label.clone()).or_default();
+                for (dst_label, count) in dsts {
+                    let new = !edge_entry.contains_key(&dst_label);
+                    *edge_entry.entry(dst_label.clone()).or_insert(0.0) += count;
+
+                    let triple = [src_label.as_str(), &edge_label, &dst_label];
+                    let sketches = get_triple(&inserted.sketches, triple);
+                    let before = get_triple(&self.sketches, triple).map(EndpointSketches::distinct);
+                    let distinct = match (sketches, before) {
+                        // the endpoints of a new triple are all inserted ones
+                        _ if new => {
+                            if let Some(sketches) = sketches {
+                                *triple_entry(&mut self.sketches, triple) = sketches.clone();
+                            }
+                            get_triple(&inserted.distinct_endpoints, triple).copied()
+                        }
+                        (Some(sketches), Some(before)) => {
+                            let entry = triple_entry(&mut self.sketches, triple);
+                            entry.merge(sketches);
+                            // the sketches still count the vertices of earlier
+                            // deletions, so the distinct endpoints grow by as much
+                            // as the sketches do
+                            let after = entry.distinct();
+                            let distinct = get_triple(&self.distinct_endpoints, triple)
+                                .copied()
+                                .unwrap_or(before);
+                            Some(DistinctEndpoints {
+                                sources: distinct.sources + after.sources - before.sources,
+                                destinations: distinct.destinations + after.destinations
+                                    - before.destinations,
+                            })
+                        }
+                        _ => None,
+                    };
+                    match distinct {
+                        Some(distinct) => {
+                            *triple_entry(&mut self.distinct_endpoints, triple) = distinct;
+                        }
+                        None => {
+                            if let Some(edges) = self.distinct_endpoints.get_mut(&src_label) {
+                                if let Some(dsts) = edges.get_mut(&edge_label) {
+                                    dsts.remove(&dst_label);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for edges in self.distinct_endpoints.values_mut() {
+            edges.retain(|_, dsts| !dsts.is_empty());
+        }
+        self.distinct_endpoints.retain(|_, edges| !edges.is_empty());
+        self.parent_labels.extend(inserted.parent_labels);
+        self.add_skipped_rows(inserted.skipped_rows);
+        self.drop_derived();
+    }
+
+    /// Removes the vertices and edges counted in `deleted`, the statistics of a
+    /// delete batch imported with the same `parent_labels`.
+    ///
+    /// Delete batches list deleted vertices without the edges deleted along
+    /// with them. These are estimated for each triple of concrete labels from
+    /// the fractions of its source and destination vertices deleted, as if
+    /// those were a uniform sample of their label, and rolled up to the
+    /// ancestors of the labels like the counts of an import. Distinct endpoints
+    /// shrink by the same fractions, while their sketches are left as they are.
+    /// The sections that cannot be updated without the vertices behind them,
+    /// see `derived_sections`, are dropped.
+    pub fn remove(&mut self, deleted: Statistics) {
+        let mut hierarchy = LabelHierarchy::default();
+        for (label, parent) in &self.parent_labels {
+            hierarchy.add_parent(label, parent);
+        }
+        let fractions = deleted
+            .vertex_cardinality
+            .iter()
+            .map(|(label, &count)| {
+                let fraction = match self.vertex_cardinality.get(label) {
+                    Some(&total) if total > count => count / total,
+                    _ => 1.0,
+                };
+                (label.as_str(), fraction)
+            })
+            .collect::<HashMap<_, _>>();
+        let kept = |label: &str| 1.0 - fractions.get(label).copied().unwrap_or(0.0);
+        let explicit = |triple: [&str; 3]| {
+            get_triple(&deleted.edge_cardinality, triple)
+                .copied()
+                .unwrap_or(0.0)
+        };
+
+        let concrete =
+            |label: &str| !label.is_empty() && !self.parent_labels.values().any(|p| p == label);
+        let mut triples = Vec::new();
+        for (src_label, edges) in &self.edge_cardinality {
+            for (edge_label, dsts) in edges {
+                for (dst_label, &count) in dsts {
+                    if concrete(src_label) && !edge_label.is_empty() && concrete(dst_label) {
+                        let triple = [src_label, edge_label, dst_label].map(String::clone);
+                        triples.push((triple, count));
+                    }
+                }
+            }
+        }
+        let mut removals: HashMap<[&str; 3], Removal> = HashMap::new();
+        for ([src_label, edge_label, dst_label], count) in &triples {
+            let triple = [src_label.as_str(), edge_label, dst_label];
+            let explicit = explicit(triple);
+            let distinct = get_triple(&self.distinct_endpoints, triple)
+                .copied()
+                .unwrap_or_default();
+            let (kept_src, kept_dst) = (kept(src_label), kept(dst_label));
+            for src_key in hierarchy.ancestry(src_label).into_iter().chain([""]) {
+                for edge_key in [edge_label.as_str(), ""] {
+                    for dst_key in hierarchy.ancestry(dst_label).into_iter().chain([""]) {
+                        let removal = removals.entry([src_key, edge_key, dst_key]).or_default();
+                        removal.edges += count;
+                        removal.explicit += explicit;
+                        removal.kept_edges += (count - explicit).max(0.0) * kept_src * kept_dst;
+                        removal.sources += distinct.sources;
+                        removal.kept_sources += distinct.sources * kept_src;
+                        removal.destinations += distinct.destinations;
+                        removal.kept_destinations += distinct.destinations * kept_dst;
+                    }
+                }
+            }
+        }
+
+        let none = Removal::default();
+        for (src_label, edges) in &mut self.edge_cardinality {
+            for (edge_label, dsts) in edges {
+                for (dst_label, count) in dsts {
+                    let triple = [src_label.as_str(), edge_label, dst_label];
+                    let removal = removals.get(&triple).unwrap_or(&none);
+                    // edges with endpoints counted under a parent label only
+                    let rest = *count - removal.edges;
+                    let rest_explicit = explicit(triple) - removal.explicit;
+                    *count = removal.kept_edges
+                        + (rest - rest_explicit).max(0.0) * kept(src_label) * kept(dst_label);
+                }
+            }
+        }
+        for (src_label, edges) in &mut self.distinct_endpoints {
+            for (edge_label, dsts) in edges {
+                for (dst_label, distinct) in dsts {
+                    let triple = [src_label.as_str(), edge_label, dst_label];
+                    let removal = removals.get(&triple).unwrap_or(&none);
+                    distinct.sources *= match removal.sources > 0.0 {
+                        true => removal.kept_sources / removal.sources,
+                        false => kept(src_label),
+                    };
+                    distinct.destinations *= match removal.destinations > 0.0 {
+                        true => removal.kept_destinations / removal.destinations,
+                        false => kept(dst_label),
+                    };
+                }
+            }
+        }
+        for (label, count) in &deleted.vertex_cardinality {
+            if let Some(total) = self.vertex_cardinality.get_mut(label) {
+                *total = (*total - count).max(0.0);
+            }
+        }
+        self.add_skipped_rows(deleted.skipped_rows);
+        self.drop_derived();
+    }
+
+    /// Returns the names of the non-empty sections that depend on which
+    /// vertices the edges connect or on the values of the vertices, which the
+    /// counts of an update do not tell: degree distributions, paths, cycles,
+    /// time series, characteristic sets and property statistics. Counting the
+    /// updated graph again restores them.
+    pub fn derived_sections(&self) -> Vec<&'static str> {
+        [
+            ("degree_distribution", self.degree_distribution.is_empty()),
+            ("path2_cardinality", self.path2_cardinality.is_empty()),
+            ("cycles", self.cycles.is_empty()),
+            ("vertex_series", self.vertex_series.is_empty()),
+            ("edge_series", self.edge_series.is_empty()),
+            ("characteristic_sets", self.characteristic_sets.is_empty()),
+            ("property_statistics", self.property_statistics.is_empty()),
+        ]
+        .into_iter()
+        .filter(|(_, empty)| !empty)
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// Drops the sections listed by `derived_sections`.
+    fn drop_derived(&mut self) {
+        self.degree_distribution.clear();
+        self.path2_cardinality.clear();
+        self.cycles.clear();
+        self.vertex_series.clear();
+        self.edge_series.clear();
+        self.characteristic_sets.clear();
+        self.property_statistics = PropertyStatistics::default();
+    }
+
+    fn add_skipped_rows(&mut self, skipped_rows: SkippedRows) {
+        for (file, reasons) in skipped_rows {
+            let file_entry = self.skipped_rows.entry(file).or_default();
+            for (reason, count) in reasons {
+                *file_entry.entry(reason).or_insert(0) += count;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sketch::hash_vertex;
+
+    fn distinct(sources: f64, destinations: f64) -> DistinctEndpoints {
+        DistinctEndpoints {
+            sources,
+            destinations,
+        }
+    }
+
+    fn sketches(ids: std::ops::Range<u64>) -> EndpointSketches {
+        let mut sketches = EndpointSketches::default();
+        for id in ids {
+            sketches.sources.insert(hash_vertex("Person", id));
+            sketches.destinations.insert(hash_vertex("Person", id));
+        }
+        sketches
+    }
+
+    fn knows() -> [&'static str; 3] {
+        ["Person", "KNOWS", "Person"]
+    }
+
+    fn characteristic_set(count: u64, multiplicities: &[(&str, f64)]) -> CharacteristicSet {
+        CharacteristicSet {
+            count,
+            multiplicities: multiplicities
+                .iter()
+                .map(|&(label, multiplicity)| (label.to_owned(), multiplicity))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn merges_sketched_distinct_endpoints_without_overcounting() {
+        let study_at = ["Person", "STUDY_AT", "University"];
+        let shard = |ids: std::ops::Range<u64>, distinct_study_at| {
+            let mut statistics = Statistics::default();
+            let sketches = sketches(ids);
+            *triple_entry(&mut statistics.distinct_endpoints, knows()) = sketches.distinct();
+            *triple_entry(&mut statistics.sketches, knows()) = sketches;
+            *triple_entry(&mut statistics.distinct_endpoints, study_at) = distinct_study_at;
+            statistics
+        };
+
+        // both shards count persons 400 to 599
+        let mut statistics = shard(0..600, distinct(3.0, 2.0));
+        statistics.merge(shard(400..1000, distinct(1.0, 1.0)));
+        let expected = sketches(0..1000);
+        assert_eq!(get_triple(&statistics.sketches, knows()), Some(&expected));
+        let merged = get_triple(&statistics.distinct_endpoints, knows()).unwrap();
+        assert_eq!(*merged, expected.distinct());
+        assert!((merged.sources - 1000.0).abs() < 50.0);
+        // exactly counted endpoints can only be summed
+        assert_eq!(
+            get_triple(&statistics.distinct_endpoints, study_at),
+            Some(&distinct(4.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn merges_characteristic_sets_with_the_same_labels() {
+        let mut sets = vec![
+            characteristic_set(3, &[("KNOWS", 2.0)]),
+            characteristic_set(1, &[("KNOWS", 1.0), ("LIKES", 4.0)]),
+        ];
+        let other = [
+            characteristic_set(3, &[("KNOWS", 1.0), ("LIKES", 2.0)]),
+            characteristic_set(2, &[("LIKES", 1.0)]),
+            characteristic_set(1, &[("KNOWS", 4.0)]),
+        ];
+        merge_characteristic_sets(&mut sets, &other);
+
+        // multiplicities are averaged over the vertices of both sets
+        // and ties are ordered by labels
+        let expected = [
+            characteristic_set(4, &[("KNOWS", 2.5)]),
+            characteristic_set(4, &[("KNOWS", 1.0), ("LIKES", 2.5)]),
+            characteristic_set(2, &[("LIKES", 1.0)]),
+        ];
+        assert_eq!(sets, expected);
+    }
+
+    #[test]
+    fn removes_edges_of_deleted_vertices_in_proportion() {
+        // counts are rolled up to "" like those of an import
+        let mut statistics = Statistics::default();
+        for label in ["Person", ""] {
+            statistics.vertex_cardinality.insert(label.to_owned(), 10.0);
+        }
+        *triple_entry(&mut statistics.edge_cardinality, knows()) = 20.0;
+        *triple_entry(&mut statistics.edge_cardinality, ["", "", ""]) = 20.0;
+        *triple_entry(&mut statistics.distinct_endpoints, knows()) = distinct(10.0, 10.0);
+        triple_entry(&mut statistics.degree_distribution, knows());
+
+        let mut deleted = Statistics::default();
+        for label in ["Person", ""] {
+            deleted.vertex_cardinality.insert(label.to_owned(), 2.0);
+        }
+        *triple_entry(&mut deleted.edge_cardinality, knows()) = 3.0;
+        *triple_entry(&mut deleted.edge_cardinality, ["", "", ""]) = 3.0;
+        statistics.remove(deleted);
+
+        // the edges not deleted explicitly survive if both endpoints do
+        let kept = 17.0 * 0.8 * 0.8;
+        assert_eq!(statistics.vertex_cardinality["Person"], 8.0);
+        assert_eq!(
+            get_triple(&statistics.edge_cardinality, knows()),
+            Some(&kept)
+        );
+        assert_eq!(
+            get_triple(&statistics.edge_cardinality, ["", "", ""]),
+            Some(&kept)
+        );
+        assert_eq!(
+            get_triple(&statistics.distinct_endpoints, knows()),
+            Some(&distinct(8.0, 8.0))
+        );
+        assert!(statistics.degree_distribution.is_empty());
+    }
+
+    #[test]
+    fn rolls_removals_up_to_parent_labels() {
+        let mut statistics = Statistics::default();
+        statistics
+            .parent_labels
+            .insert("City".to_owned(), "Place".to_owned());
+        for (label, count) in [("Person", 4.0), ("City", 5.0), ("Place", 10.0)] {
+            statistics
+                .vertex_cardinality
+                .insert(label.to_owned(), count);
+        }
+        // two of the edges lead to places without a concrete label
+        let city = ["Person", "IS_LOCATED_IN", "City"];
+        let place = ["Person", "IS_LOCATED_IN", "Place"];
+        *triple_entry(&mut statistics.edge_cardinality, city) = 8.0;
+        *triple_entry(&mut statistics.edge_cardinality, place) = 10.0;
+
+        let mut deleted = Statistics::default();
+        deleted.vertex_cardinality.insert("City".to_owned(), 1.0);
+        deleted.vertex_cardinality.insert("Place".to_owned(), 1.0);
+        statistics.remove(deleted);
+
+        assert_eq!(statistics.vertex_cardinality["City"], 4.0);
+        assert_eq!(statistics.vertex_cardinality["Place"], 9.0);
+        assert_eq!(get_triple(&statistics.edge_cardinality, city), Some(&6.4));
+        let rolled_up = get_triple(&statistics.edge_cardinality, place).unwrap();
+        assert!((rolled_up - (6.4 + 2.0 * 0.9)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn applies_inserts_between_existing_vertices() {
+        let likes = ["Person", "LIKES", "Post"];
+        let interests = ["Person", "HAS_INTEREST", "Tag"];
+        let mut statistics = Statistics::default();
+        statistics
+            .vertex_cardinality
+            .insert("Person".to_owned(), 10.0);
+        *triple_entry(&mut statistics.edge_cardinality, knows()) = 20.0;
+        *triple_entry(&mut statistics.edge_cardinality, likes) = 5.0;
+        *triple_entry(&mut statistics.distinct_endpoints, knows()) = distinct(10.0, 10.0);
+        *triple_entry(&mut statistics.distinct_endpoints, likes) = distinct(3.0, 5.0);
+        triple_entry(&mut statistics.degree_distribution, likes);
+
+        let mut inserted = Statistics::default();
+        *triple_entry(&mut inserted.edge_cardinality, knows()) = 4.0;
+        *triple_entry(&mut inserted.edge_cardinality, interests) = 3.0;
+        *triple_entry(&mut inserted.distinct_endpoints, knows()) = distinct(2.0, 2.0);
+        *triple_entry(&mut inserted.distinct_endpoints, interests) = distinct(2.0, 3.0);
+        statistics.apply_inserts(inserted);
+
+        assert_eq!(statistics.vertex_cardinality["Person"], 10.0);
+        assert_eq!(
+            get_triple(&statistics.edge_cardinality, knows()),
+            Some(&24.0)
+        );
+        // the inserted edges may connect vertices that already had some
+        assert_eq!(get_triple(&statistics.distinct_endpoints, knows()), None);
+        assert_eq!(
+            get_triple(&statistics.distinct_endpoints, likes),
+            Some(&distinct(3.0, 5.0))
+        );
+        assert_eq!(
+            get_triple(&statistics.distinct_endpoints, interests),
+            Some(&distinct(2.0, 3.0))
+        );
+        assert!(statistics.degree_distribution.is_empty());
+    }
+
+    #[test]
+    fn grows_distinct_endpoints_with_their_sketches() {
+        let mut statistics = Statistics::default();
+        *triple_entry(&mut statistics.edge_cardinality, knows()) = 20.0;
+        *triple_entry(&mut statistics.sketches, knows()) = sketches(0..1000);
+        // after deleting a fifth of the vertices
+        *triple_entry(&mut statistics.distinct_endpoints, knows()) = distinct(800.0, 800.0);
+
+        let mut inserted = Statistics::default();
+        *triple_entry(&mut inserted.edge_cardinality, knows()) = 4.0;
+        *triple_entry(&mut inserted.sketches, knows()) = sketches(500..1500);
+        statistics.apply_inserts(inserted);
+
+        let growth = sketches(0..1500).distinct().sources - sketches(0..1000).distinct().sources;
+        let sources = get_triple(&statistics.distinct_endpoints, knows())
+            .unwrap()
+            .sources;
+        assert_eq!(sources, 800.0 + growth);
+        assert!((sources - 1300.0).abs() < 100.0, "{}", sources);
+    }
+}
@@ -0,0 +1,40 @@
+//! Fixtures shared by the unit tests.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A directory below the system's temporary directory, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory, named after the test to keep tests running
+    /// in parallel apart.
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("ldbc_stat_gen_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes `contents` to the file at relative path `name`, creating its
+    /// parent directories.
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}